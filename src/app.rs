@@ -1,20 +1,73 @@
-use rusqlite::{params, Connection};
-use anyhow::Result;
-use log::info;
-use std::collections::HashMap;
-use crate::labelcodes::load_labelcodes;
-use crate::model::TrackInfo;
+use rusqlite::Connection;
+use anyhow::{anyhow, Result};
+use log::{error, info};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hasher;
+use std::path::Path;
+use std::sync::Arc;
+use crate::cache::{CacheEntry, CacheState, ParseCache, StableHasher};
+use crate::diagnostic::{push_diagnostic, Diagnostic, DiagnosticKind, Severity};
+use crate::filename_rules::{FilenameRules, VersionSuffixes, FILENAME_RULES_PATH, VERSION_SUFFIXES_PATH};
+use crate::labelcodes::LabelCodes;
+use crate::enrich::{
+    database_hash, delete_database_row, load_database_row, write_database_row, Candidate, DatabaseLookup, DatabaseRow,
+    MatchCandidates, RIGHTS_HOLDERS_SCHEMA,
+};
+use crate::history::{Command, FileList, History, TrackChange};
+use crate::model::{RightsHolder, TrackField, TrackInfo, TrackOverride};
+use crate::settings::{load_settings, Settings, SETTINGS_PATH};
+use crate::parser::ParseContext;
+use crate::production::{ProductionGroup, ProductionInfo};
+use crate::project::{
+    load_recent_projects, push_recent_project, save_recent_projects, source_state, Project, ProjectSource,
+    SourceState, PROJECT_VERSION, RECENT_PROJECTS_PATH,
+};
+use crate::timecode::{FrameRate, Timecode};
+use crate::worker::{ParseJob, ParseOutcome, ParseRequest, WorkerMessage};
+
+pub const DATABASE_PATH: &str = "src/assets/databank.db";
 
 
 pub struct GemaLauncherApp {
     // Bestehende Felder
     pub filenames: Vec<String>,
-    pub error_messages: Vec<String>,
-    pub label_dict: HashMap<String, String>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub label_codes: Arc<LabelCodes>,
+    pub filename_rules: Arc<FilenameRules>,
     pub selected_files: Vec<bool>,
     pub tracks_per_file: HashMap<String, Vec<TrackInfo>>,
     pub export_path: Option<String>,
     pub db_connection: Option<Connection>,
+    pub settings: Settings,
+    // Laufendes Einlesen im Hintergrund
+    pub parse_job: Option<ParseJob>,
+    // Ergebnisse je Datei; nur geänderte Dateien werden neu eingelesen
+    pub parse_cache: ParseCache,
+    // Inhalt der Datenbank als Hash, Teil des Cache-Schlüssels (0 = keine Datenbank)
+    pub database_stamp: u64,
+    // Meldungen, die nicht zum gespeicherten Ergebnis einer Datei gehören
+    // (allgemeine Meldungen der Durchläufe und alles über `report`)
+    pub general_diagnostics: Vec<Diagnostic>,
+    // Von Hand geänderte Tracks: Datei -> Zeile (`TrackInfo::key`) -> Änderungen
+    pub track_overrides: HashMap<String, HashMap<String, TrackOverride>>,
+    // Indizes, deren Datenbank-Vorschläge abgelehnt wurden
+    pub dismissed_candidates: HashSet<String>,
+    // Rückgängig/Wiederholen für Track-, Dateilisten- und Datenbank-Änderungen
+    pub history: History,
+    pub show_history_dialog: bool,
+
+    // Produktionsdaten je Datei bzw. Dateigruppe und die im Dialog gewählte Gruppe
+    pub productions: Vec<ProductionGroup>,
+    pub selected_production: usize,
+    pub show_production_dialog: bool,
+
+    // Geöffnete Projektdatei und zuletzt benutzte Projekte
+    pub project_path: Option<String>,
+    pub recent_projects: Vec<String>,
+
+    // Bildraten je Eingabedatei
+    pub frame_rate_overrides: HashMap<String, FrameRate>,
+    pub detected_frame_rates: HashMap<String, FrameRate>,
     
     // Neue Felder für UI
     pub show_csv_preview: bool,
@@ -22,12 +75,33 @@ pub struct GemaLauncherApp {
     pub show_db_update_dialog: bool,
     pub show_db_search_dialog: bool,
     pub track_search_query: String,
+    pub show_settings_dialog: bool,
+    pub show_filename_rules_dialog: bool,
+    pub show_match_dialog: bool,
+    pub filename_rule_test: String,
+    // Eingabe für den Namen der Dauer-Regel bzw. des Exportprofils, solange sie bearbeitet wird
+    pub policy_name_edit: Option<String>,
+    pub profile_name_edit: Option<String>,
+    // Meldungsliste: ausgeblendete Schweregrade und Sprungziel (Datei, Index)
+    pub hidden_severities: Vec<Severity>,
+    pub focused_track: Option<(String, String)>,
+    pub scroll_to_focused_track: bool,
+    // Track im Bearbeiten-Fenster (Datei, Zeile) und Eingaben
+    pub track_edit: Option<(String, String)>,
+    pub track_edit_values: TrackOverride,
+    // Zelle der Track-Übersicht, die gerade bearbeitet wird (Datei, Zeile, Feld), und Eingabe
+    pub cell_edit: Option<(String, String, TrackField)>,
+    pub cell_edit_text: String,
     
     // Felder für Datenbank-Aktualisierung
     pub db_update_index: String,
     pub db_update_title: String,
     pub db_update_artist: String,
     pub db_update_labelcode: String,
+    pub db_update_rights: Vec<RightsHolder>,
+    // Index, dessen Beteiligte im Editor stehen, und ob sie seitdem bearbeitet wurden
+    pub db_update_rights_index: Option<String>,
+    pub db_update_rights_edited: bool,
     pub db_update_status: String,
     
     // Felder für Datenbank-Suche
@@ -45,12 +119,32 @@ impl Default for GemaLauncherApp {
         let mut app = Self {
             // Bestehende Felder
             filenames: Vec::new(),
-            error_messages: Vec::new(),
-            label_dict: load_labelcodes("src/assets/labelcodes.json").unwrap_or_default(),
+            diagnostics: Vec::new(),
+            label_codes: Arc::new(LabelCodes::load("src/assets/labelcodes.json").unwrap_or_default()),
+            filename_rules: Arc::default(),
             selected_files: Vec::new(),
             tracks_per_file: HashMap::new(),
             export_path: None,
             db_connection: None,
+            settings: load_settings(SETTINGS_PATH).unwrap_or_default(),
+            parse_job: None,
+            parse_cache: ParseCache::default(),
+            database_stamp: 0,
+            general_diagnostics: Vec::new(),
+            track_overrides: HashMap::new(),
+            dismissed_candidates: HashSet::new(),
+            history: History::default(),
+            show_history_dialog: false,
+
+            productions: Vec::new(),
+            selected_production: 0,
+            show_production_dialog: false,
+
+            project_path: None,
+            recent_projects: load_recent_projects(RECENT_PROJECTS_PATH),
+
+            frame_rate_overrides: HashMap::new(),
+            detected_frame_rates: HashMap::new(),
             
             // Neue Felder initialisieren
             show_csv_preview: false,
@@ -58,11 +152,27 @@ impl Default for GemaLauncherApp {
             show_db_update_dialog: false,
             show_db_search_dialog: false,
             track_search_query: String::new(),
+            show_settings_dialog: false,
+            show_filename_rules_dialog: false,
+            show_match_dialog: false,
+            filename_rule_test: String::new(),
+            policy_name_edit: None,
+            profile_name_edit: None,
+            hidden_severities: Vec::new(),
+            focused_track: None,
+            scroll_to_focused_track: false,
+            track_edit: None,
+            track_edit_values: TrackOverride::default(),
+            cell_edit: None,
+            cell_edit_text: String::new(),
             
             db_update_index: String::new(),
             db_update_title: String::new(),
             db_update_artist: String::new(),
             db_update_labelcode: String::new(),
+            db_update_rights: Vec::new(),
+            db_update_rights_index: None,
+            db_update_rights_edited: false,
             db_update_status: String::new(),
             
             db_search_query: String::new(),
@@ -73,14 +183,56 @@ impl Default for GemaLauncherApp {
             db_search_in_labelcode: true,
         };
 
-        if let Err(e) = app.connect_to_database("src/assets/databank.db") {
-            app.error_messages.push(format!("Datenbank konnte nicht geladen werden: {}", e));
+        app.reload_filename_rules();
+
+        if let Err(e) = app.connect_to_database(DATABASE_PATH) {
+            app.report(Diagnostic::error(
+                DiagnosticKind::Database,
+                format!("Datenbank konnte nicht geladen werden: {}", e),
+            ));
         }
         app
     }
 }
 
 impl GemaLauncherApp {
+    /// Nimmt eine Meldung auf und protokolliert sie; identische Meldungen nur einmal.
+    /// Sie bleibt auch beim Neuaufbau der Track-Übersicht erhalten.
+    pub fn report(&mut self, diagnostic: Diagnostic) {
+        if !self.general_diagnostics.contains(&diagnostic) {
+            self.general_diagnostics.push(diagnostic.clone());
+        }
+        push_diagnostic(&mut self.diagnostics, diagnostic);
+    }
+
+    /// Lädt die Dateinamen-Regeln und Versionsendungen neu; Fehler landen in den Meldungen.
+    /// Die Regeln bestimmen Index/Titel/Künstler, daher werden alle Dateien neu eingelesen.
+    pub fn reload_filename_rules(&mut self) {
+        match FilenameRules::load(FILENAME_RULES_PATH) {
+            Ok(mut rules) => {
+                match VersionSuffixes::load(VERSION_SUFFIXES_PATH) {
+                    Ok(versions) => rules = rules.with_versions(versions),
+                    Err(e) => rules.errors.push(
+                        Diagnostic::error(
+                            DiagnosticKind::Config,
+                            format!("Versionsendungen konnten nicht geladen werden: {:#}", e),
+                        )
+                        .file(VERSION_SUFFIXES_PATH),
+                    ),
+                }
+                self.diagnostics.extend(rules.errors.iter().cloned());
+                self.filename_rules = Arc::new(rules);
+            }
+            Err(e) => self.report(
+                Diagnostic::error(
+                    DiagnosticKind::Config,
+                    format!("Dateinamen-Regeln konnten nicht geladen werden: {:#}", e),
+                )
+                .file(FILENAME_RULES_PATH),
+            ),
+        }
+    }
+
     fn connect_to_database(&mut self, path: &str) -> Result<()> {
         let conn = Connection::open(path)?;
         
@@ -99,7 +251,11 @@ impl GemaLauncherApp {
             CREATE INDEX IF NOT EXISTS idx_artist ON my_table(kuenstler COLLATE NOCASE);
             CREATE INDEX IF NOT EXISTS idx_labelcode ON my_table(labelcode COLLATE NOCASE);
         ")?;
-        
+
+        // Beteiligte (Komponist, Textdichter, Bearbeiter, Verlag) je Werk
+        conn.execute_batch(RIGHTS_HOLDERS_SCHEMA)?;
+
+        self.database_stamp = database_hash(&conn)?;
         self.db_connection = Some(conn);
         info!("Optimierte Verbindung zur SQLite-Datenbank hergestellt.");
         Ok(())
@@ -116,6 +272,17 @@ impl GemaLauncherApp {
         }
     }
 
+    /// Fügt mehrere Dateien als ein Schritt im Verlauf hinzu und liest sie ein.
+    pub fn add_files(&mut self, paths: Vec<String>) {
+        let before = self.file_list();
+        let count = paths.len();
+        for path in paths {
+            self.add_file(path);
+        }
+        self.record_file_change(format!("{} Datei(en) hinzugefügt", count), before);
+        self.parse_filenames();
+    }
+
     pub fn vacuum_database(&mut self) -> Result<()> {
         if let Some(conn) = &self.db_connection {
             conn.execute_batch("VACUUM;")?;
@@ -138,6 +305,7 @@ impl GemaLauncherApp {
 
     /// Löscht alle ausgewählten Dateien.
     pub fn delete_selected_files(&mut self) {
+        let before = self.file_list();
         let expected_size = self.filenames.len() - self.selected_files.iter().filter(|&&x| x).count();
         let mut new_filenames = Vec::with_capacity(expected_size);
         let mut new_selected_files = Vec::with_capacity(expected_size);
@@ -153,17 +321,197 @@ impl GemaLauncherApp {
 
         self.filenames = new_filenames;
         self.selected_files = new_selected_files;
-        let _ = crate::parser::parse_all_files(self);
+        for group in &mut self.productions {
+            group.files.retain(|f| self.filenames.contains(f));
+        }
+        self.record_file_change("Ausgewählte Dateien entfernt", before);
+        self.parse_filenames();
     }
 
     /// Löscht alle Dateien.
     pub fn delete_all_files(&mut self) {
+        let before = self.file_list();
+        self.cancel_parse_job();
         self.filenames.clear();
         self.selected_files.clear();
         self.tracks_per_file.clear();
+        self.frame_rate_overrides.clear();
+        self.detected_frame_rates.clear();
+        self.parse_cache.clear();
+        self.track_overrides.clear();
+        self.dismissed_candidates.clear();
+        for group in &mut self.productions {
+            group.files.clear();
+        }
+        self.record_file_change("Alle Dateien entfernt", before);
         info!("Alle Dateien gelöscht.");
     }
 
+    /// Setzt die Bildrate einer Datei (`None` = automatisch) und liest sie neu ein.
+    pub fn set_frame_rate_override(&mut self, file: &str, rate: Option<FrameRate>) {
+        let before = self.file_list();
+        match rate {
+            Some(rate) => self.frame_rate_overrides.insert(file.to_string(), rate),
+            None => self.frame_rate_overrides.remove(file),
+        };
+        self.record_file_change(format!("Bildrate von {} geändert", file_label(file)), before);
+        self.parse_filenames();
+    }
+
+    /// Stand der Dateiliste für den Verlauf.
+    pub fn file_list(&self) -> FileList {
+        FileList {
+            filenames: self.filenames.clone(),
+            frame_rate_overrides: self.frame_rate_overrides.clone(),
+            track_overrides: self.track_overrides.clone(),
+            dismissed_candidates: self.dismissed_candidates.clone(),
+            productions: self.productions.clone(),
+        }
+    }
+
+    fn record_file_change(&mut self, label: impl Into<String>, before: FileList) {
+        let after = self.file_list();
+        if before != after {
+            self.history.record(label, Command::Files { before, after });
+        }
+    }
+
+    /// Stellt eine Dateiliste wieder her; fehlende Ergebnisse werden neu eingelesen.
+    fn restore_file_list(&mut self, files: &FileList) {
+        self.filenames = files.filenames.clone();
+        self.selected_files = vec![false; self.filenames.len()];
+        self.frame_rate_overrides = files.frame_rate_overrides.clone();
+        self.track_overrides = files.track_overrides.clone();
+        self.dismissed_candidates = files.dismissed_candidates.clone();
+        self.productions = files.productions.clone();
+        self.selected_production = 0;
+        self.parse_filenames();
+    }
+
+    /// Produktionsdaten der Gruppe, zu der die Datei gehört.
+    pub fn production_for(&self, file: &str) -> Option<&ProductionInfo> {
+        self.productions
+            .iter()
+            .find(|group| group.files.iter().any(|f| f == file))
+            .map(|group| &group.info)
+    }
+
+    /// Ordnet eine Datei einer Gruppe zu (oder keiner); eine Datei gehört zu höchstens einer Gruppe.
+    pub fn assign_production(&mut self, file: &str, group: Option<usize>) {
+        for (i, production) in self.productions.iter_mut().enumerate() {
+            production.files.retain(|f| f != file);
+            if Some(i) == group {
+                production.files.push(file.to_string());
+            }
+        }
+    }
+
+    /// Speichert Dateiliste, Ergebnisse, Handänderungen und Produktionsdaten als Projekt.
+    pub fn save_project(&mut self, path: &str) -> Result<()> {
+        let sources = self
+            .filenames
+            .iter()
+            .map(|file| ProjectSource {
+                path: file.clone(),
+                frame_rate_override: self.frame_rate_overrides.get(file).copied(),
+                result: self.parse_cache.get(file).cloned(),
+            })
+            .collect();
+        let project = Project {
+            version: PROJECT_VERSION,
+            sources,
+            track_overrides: self.track_overrides.clone(),
+            dismissed_candidates: self.dismissed_candidates.clone(),
+            productions: self.productions.clone(),
+            export_path: self.export_path.clone(),
+            duration_policy: Some(self.settings.active_duration_policy.clone()),
+        };
+        project.save(path)?;
+        self.project_path = Some(path.to_string());
+        self.remember_project(path);
+        Ok(())
+    }
+
+    /// Öffnet ein Projekt und zeigt den gespeicherten Stand. Eingabedateien, die sich
+    /// seitdem geändert haben oder fehlen, werden als Warnung gemeldet.
+    pub fn open_project(&mut self, path: &str) -> Result<()> {
+        let project = Project::load(path)?;
+        self.delete_all_files();
+        self.general_diagnostics.clear();
+        self.track_overrides = project.track_overrides;
+        self.dismissed_candidates = project.dismissed_candidates;
+        self.productions = project.productions;
+        self.selected_production = 0;
+        self.export_path = project.export_path;
+        if let Some(name) = project.duration_policy {
+            if self.settings.duration_policies.iter().any(|p| p.name == name) {
+                self.settings.active_duration_policy = name;
+            } else {
+                self.report(
+                    Diagnostic::warning(
+                        DiagnosticKind::Config,
+                        format!(
+                            "Dauer-Regel '{}' aus dem Projekt gibt es nicht mehr, verwende '{}'.",
+                            name,
+                            self.settings.duration_policy().name
+                        ),
+                    )
+                    .file(path)
+                    .suggest("Regel unter Einstellungen → Dauer-Regel anlegen oder eine andere wählen."),
+                );
+            }
+        }
+
+        for source in project.sources {
+            let file = source.path;
+            if self.filenames.contains(&file) {
+                continue;
+            }
+            self.filenames.push(file.clone());
+            self.selected_files.push(false);
+            if let Some(rate) = source.frame_rate_override {
+                self.frame_rate_overrides.insert(file.clone(), rate);
+            }
+            let Some(mut entry) = source.result else {
+                continue;
+            };
+            match source_state(&file, entry.fingerprint) {
+                SourceState::Unchanged(fingerprint) => entry.fingerprint = Some(fingerprint),
+                SourceState::Changed => entry.diagnostics.push(
+                    Diagnostic::warning(
+                        DiagnosticKind::File,
+                        "Datei wurde seit dem Speichern des Projekts geändert, angezeigt wird der gespeicherte Stand.",
+                    )
+                    .file(&file)
+                    .suggest("Datei → \"Geänderte Dateien neu einlesen\" übernimmt den aktuellen Inhalt, Handänderungen bleiben erhalten."),
+                ),
+                SourceState::Missing => entry.diagnostics.push(
+                    Diagnostic::warning(
+                        DiagnosticKind::File,
+                        "Datei nicht gefunden, angezeigt wird der gespeicherte Stand.",
+                    )
+                    .file(&file)
+                    .suggest("Datei wiederherstellen oder aus der Liste entfernen."),
+                ),
+            }
+            self.parse_cache.insert(file, entry);
+        }
+
+        self.rebuild_tracks();
+        self.history.clear();
+        self.project_path = Some(path.to_string());
+        self.remember_project(path);
+        Ok(())
+    }
+
+    /// Setzt ein Projekt an den Anfang der zuletzt benutzten Projekte.
+    fn remember_project(&mut self, path: &str) {
+        push_recent_project(&mut self.recent_projects, path);
+        if let Err(e) = save_recent_projects(RECENT_PROJECTS_PATH, &self.recent_projects) {
+            error!("{:#}", e);
+        }
+    }
+
     /// Formatiert die Dauer in Sekunden in ein Format "S:MM".
     pub fn format_duration(&self, seconds: f64) -> String {
         let total_hundredths = (seconds * 100.0).round() as i64;
@@ -172,71 +520,568 @@ impl GemaLauncherApp {
         format!("{}:{:02}", s, ms)
     }
 
-    /// Parst die Dateinamen/Tracks und wendet danach die Datenbank an.
-    pub fn parse_filenames(&mut self) -> Result<()> {
-        crate::parser::parse_all_files(self)?;
-        self.apply_database_info();
+    /// Gegenstück zu `format_duration`: "S:MM" (Sekunden:Hundertstel), Sekunden ("75,5")
+    /// oder ein Timecode "HH:MM:SS:FF" in der angegebenen Bildrate.
+    pub fn parse_duration(&self, text: &str, rate: FrameRate) -> Option<Timecode> {
+        let text = text.trim();
+        if let Some(timecode) = Timecode::parse(text, rate) {
+            return Some(timecode);
+        }
+        let hundredths = match text.split_once(':') {
+            Some((seconds, fraction)) => {
+                if fraction.is_empty() || fraction.len() > 2 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                let seconds = i64::from(seconds.parse::<u32>().ok()?);
+                // eine Stelle = Zehntel ("75:5" = 75,5 Sekunden)
+                let scale = if fraction.len() == 1 { 10 } else { 1 };
+                seconds * 100 + fraction.parse::<i64>().ok()? * scale
+            }
+            None => {
+                let seconds: f64 = text.replace(',', ".").parse().ok()?;
+                if !seconds.is_finite() {
+                    return None;
+                }
+                (seconds * 100.0).round() as i64
+            }
+        };
+        (hundredths >= 0).then(|| Timecode::from_rational_seconds(hundredths, 100, rate))
+    }
+
+    /// Startet Einlesen und Datenbank-Abgleich im Hintergrund. Ein laufender
+    /// Durchlauf wird abgebrochen; die Ergebnisse übernimmt `poll_parse_job`.
+    /// Dateien, deren Inhalt und Einstellungen sich seit dem letzten Durchlauf
+    /// nicht geändert haben, kommen aus dem Cache.
+    pub fn parse_filenames(&mut self) {
+        self.cancel_parse_job();
+        self.parse_cache.retain_files(&self.filenames);
+
+        let requests: Vec<ParseRequest> = self
+            .filenames
+            .iter()
+            .filter_map(|path| match self.parse_cache.state(path, self.parse_config(path)) {
+                CacheState::Current => None,
+                CacheState::Modified(hash) => Some(ParseRequest { path: path.clone(), known_hash: Some(hash) }),
+                CacheState::Missing => Some(ParseRequest { path: path.clone(), known_hash: None }),
+            })
+            .collect();
+
+        if requests.is_empty() {
+            info!("Alle Dateien unverändert, nichts neu einzulesen.");
+            self.rebuild_tracks();
+            return;
+        }
+        info!("{} von {} Dateien werden neu eingelesen.", requests.len(), self.filenames.len());
+        let ctx = ParseContext::from_app(self);
+        let db_path = self.db_connection.is_some().then(|| DATABASE_PATH.to_string());
+        self.parse_job = Some(ParseJob::start(ctx, requests, db_path));
+    }
+
+    /// Schlüssel für alles, was außer dem Dateiinhalt das Ergebnis einer Datei bestimmt:
+    /// Einlese-Einstellungen, Dateinamen-Regeln, Bildrate der Datei, Labelcodes und Datenbankinhalt.
+    fn parse_config(&self, path: &str) -> u64 {
+        let mut hasher = StableHasher::default();
+        hasher.write(&self.settings.parse_hash().to_le_bytes());
+        hasher.write(&self.filename_rules.content_hash().to_le_bytes());
+        hasher.write_str(&self.frame_rate_overrides.get(path).map(|rate| rate.to_string()).unwrap_or_default());
+        hasher.write(&self.label_codes.content_hash().to_le_bytes());
+        hasher.write(&self.database_stamp.to_le_bytes());
+        hasher.finish()
+    }
+
+    /// Übernimmt nach einer eigenen Datenbank-Änderung den neuen Datenbankinhalt in den
+    /// Cache-Schlüssel. Einträge, die bisher aktuell waren, bleiben es, da die betroffenen
+    /// Tracks anschließend neu abgeglichen werden.
+    fn update_database_stamp(&mut self) -> Result<()> {
+        let Some(conn) = &self.db_connection else {
+            return Ok(());
+        };
+        let stamp = database_hash(conn)?;
+        let current: Vec<String> = self
+            .filenames
+            .iter()
+            .filter(|path| self.parse_cache.get(path).is_some_and(|entry| entry.config == self.parse_config(path)))
+            .cloned()
+            .collect();
+        self.database_stamp = stamp;
+        for path in current {
+            let config = self.parse_config(&path);
+            if let Some(entry) = self.parse_cache.get_mut(&path) {
+                entry.config = config;
+            }
+        }
         Ok(())
     }
-    
-    /// Sucht in der Datenbank nach passender Zeile (passend zum Index) und überschreibt Titel/Künstler/Labelcode, falls gefunden.
-    fn apply_database_info(&mut self) {
-        let Some(conn) = self.db_connection.as_ref() else {
-            info!("Keine Datenbankverbindung vorhanden. Überspringe apply_database_info().");
+
+    /// Setzt Tracks, Bildraten und Meldungen aus dem Cache zusammen
+    /// und wendet die Handänderungen an.
+    pub fn rebuild_tracks(&mut self) {
+        self.tracks_per_file.clear();
+        self.detected_frame_rates.clear();
+        self.diagnostics = self.general_diagnostics.clone();
+
+        for path in &self.filenames {
+            let Some(entry) = self.parse_cache.get(path) else {
+                continue;
+            };
+            let mut tracks = entry.tracks.clone();
+            if let Some(overrides) = self.track_overrides.get(path) {
+                for track in tracks.iter_mut() {
+                    if let Some(track_override) = overrides.get(&track.key) {
+                        track_override.apply(track);
+                    }
+                }
+            }
+            if !tracks.is_empty() {
+                self.tracks_per_file.insert(path.clone(), tracks);
+            }
+            if let Some(rate) = entry.detected_rate {
+                self.detected_frame_rates.insert(path.clone(), rate);
+            }
+            for diagnostic in &entry.diagnostics {
+                push_diagnostic(&mut self.diagnostics, diagnostic.clone());
+            }
+        }
+    }
+
+    /// Gleicht nach einer Datenbank-Änderung (auch Rückgängig/Wiederholen) nur die Tracks mit
+    /// diesem Index neu ab. Sie starten dabei vom eingelesenen Stand, damit nach einem entfernten
+    /// oder zurückgenommenen Eintrag keine alten Datenbank-Werte stehen bleiben.
+    pub fn reenrich_index(&mut self, index: &str) {
+        if let Err(e) = self.update_database_stamp() {
+            self.report(Diagnostic::error(
+                DiagnosticKind::Database,
+                format!("Datenbankinhalt konnte nicht gelesen werden: {}", e),
+            ));
+        }
+        let Some(conn) = &self.db_connection else {
             return;
         };
-    
-        // Prepare the query once
-        let query = r#"
-            SELECT kuenstler, titel, labelcode
-            FROM my_table
-            WHERE LOWER("index") = LOWER(?1)
-            LIMIT 1
-        "#;
-    
-        let mut stmt = match conn.prepare(query) {
-            Ok(s) => {
-                info!("Abfrage vorbereitet.");
-                s
-            },
+        let result = DatabaseLookup::new(conn).map(|mut lookup| {
+            for (path, entry) in self.parse_cache.entries_mut() {
+                for track in entry.tracks.iter_mut().filter(|t| t.index.eq_ignore_ascii_case(index)) {
+                    let restored = track.restore_parsed();
+                    if lookup.enrich(track, &self.label_codes) {
+                        // Jetzt exakter Treffer: Vorschläge und Meldungen dazu erledigt
+                        entry.candidates.retain(|c| !c.index.eq_ignore_ascii_case(index));
+                        entry.diagnostics.retain(|d| {
+                            d.kind != DiagnosticKind::Database
+                                || !d.track_index.as_deref().is_some_and(|i| i.eq_ignore_ascii_case(index))
+                        });
+                    } else if restored {
+                        push_diagnostic(
+                            &mut entry.diagnostics,
+                            Diagnostic::new(
+                                Severity::Info,
+                                DiagnosticKind::Database,
+                                format!("'{}' nicht in der Datenbank, eingelesene Werte werden verwendet", track.index),
+                            )
+                            .file(path)
+                            .track(&track.index)
+                            .suggest("Eintrag über Datenbank → \"Datensatz aktualisieren\" anlegen"),
+                        );
+                    }
+                }
+            }
+        });
+        self.rebuild_tracks();
+        if let Err(e) = result {
+            self.report(Diagnostic::error(
+                DiagnosticKind::Database,
+                format!("Fehler beim Vorbereiten der Abfrage: {}", e),
+            ));
+        }
+    }
+
+    /// Offene Datenbank-Vorschläge je Datei. Tracks mit Handänderungen
+    /// (auch übernommenen Vorschlägen) und abgelehnte Indizes fehlen.
+    pub fn pending_matches(&self) -> Vec<(String, MatchCandidates)> {
+        let mut pending = Vec::new();
+        for path in &self.filenames {
+            let Some(entry) = self.parse_cache.get(path) else {
+                continue;
+            };
+            let overrides = self.track_overrides.get(path);
+            for found in &entry.candidates {
+                let edited = overrides.is_some_and(|o| {
+                    entry.tracks.iter().any(|t| t.index == found.index && o.contains_key(&t.key))
+                });
+                if !edited && !self.dismissed_candidates.contains(&found.index) {
+                    pending.push((path.clone(), found.clone()));
+                }
+            }
+        }
+        pending
+    }
+
+    /// Übernimmt einen Vorschlag für alle Dateien mit diesem Index, als Handänderung.
+    pub fn confirm_candidate(&mut self, index: &str, candidate: &Candidate) {
+        let rights_holders = self.load_rights_holders(&candidate.index);
+        let mut changes = Vec::new();
+        let tracks: Vec<(String, TrackInfo)> = self
+            .filenames
+            .iter()
+            .filter_map(|path| Some((path, &self.parse_cache.get(path)?.tracks)))
+            .flat_map(|(path, tracks)| tracks.iter().filter(|t| t.index == index).map(|t| (path.clone(), t.clone())))
+            .collect();
+        for (path, mut track) in tracks {
+            candidate.apply(&mut track, &self.label_codes);
+            let track_override = self.edited_override(&path, &track.key, |o| {
+                o.titel = Some(track.titel.clone());
+                o.kuenstler = Some(track.kuenstler.clone());
+                o.label_code = Some(track.label_code.clone());
+                if !rights_holders.is_empty() {
+                    o.rights_holders = Some(rights_holders.clone());
+                }
+            });
+            changes.push((path, track.key, track_override));
+        }
+        // Stimmt der Vorschlag mit dem eingelesenen Stand überein, bleibt keine Handänderung;
+        // der Vorschlag soll trotzdem nicht wieder angeboten werden
+        if changes.iter().all(|(_, _, track_override)| track_override.is_empty()) {
+            self.dismiss_candidates(index);
+        }
+        info!("Vorschlag '{}' für '{}' übernommen.", candidate.index, index);
+        self.change_tracks(format!("Vorschlag '{}' für '{}' übernommen", candidate.index, index), changes);
+    }
+
+    /// Beteiligte eines Werks aus der Datenbank; leer ohne Verbindung oder Einträge.
+    pub fn load_rights_holders(&self, index: &str) -> Vec<RightsHolder> {
+        let Some(conn) = &self.db_connection else {
+            return Vec::new();
+        };
+        DatabaseLookup::new(conn)
+            .map(|mut lookup| lookup.rights_holders(index))
+            .unwrap_or_default()
+    }
+
+    pub fn dismiss_candidates(&mut self, index: &str) {
+        self.dismissed_candidates.insert(index.to_string());
+    }
+
+    /// Eingelesener und abgeglichener Stand eines Tracks (Zeile `key`), ohne Handänderungen.
+    pub fn original_track(&self, file: &str, key: &str) -> Option<&TrackInfo> {
+        self.parse_cache.get(file)?.tracks.iter().find(|t| t.key == key)
+    }
+
+    /// Index einer Zeile für Verlauf und Meldungen.
+    fn track_index(&self, file: &str, key: &str) -> String {
+        self.original_track(file, key).map_or_else(String::new, |t| t.index.clone())
+    }
+
+    /// Handänderungen einer Zeile nach `edit`, ausgehend von den bisherigen. Alle
+    /// Bearbeitungswege (Zelle, Bearbeiten-Fenster, Vorschläge) ändern so nur ihre Felder;
+    /// was dem eingelesenen Stand entspricht, wird verworfen.
+    pub fn edited_override(&self, file: &str, key: &str, edit: impl FnOnce(&mut TrackOverride)) -> TrackOverride {
+        let mut track_override = self
+            .track_overrides
+            .get(file)
+            .and_then(|overrides| overrides.get(key))
+            .cloned()
+            .unwrap_or_default();
+        edit(&mut track_override);
+        if let Some(original) = self.original_track(file, key) {
+            track_override.drop_unchanged(original);
+        }
+        track_override
+    }
+
+    /// Speichert die Handänderungen eines Tracks; leere Änderungen werden verworfen.
+    pub fn set_track_override(&mut self, file: &str, key: &str, track_override: TrackOverride) {
+        let label = format!("Track '{}' bearbeitet", self.track_index(file, key));
+        self.change_tracks(label, vec![(file.to_string(), key.to_string(), track_override)]);
+    }
+
+    /// Speichert Handänderungen (Datei, Zeile, Änderungen) als ein Schritt im Verlauf.
+    fn change_tracks(&mut self, label: String, changes: Vec<(String, String, TrackOverride)>) {
+        let changes: Vec<TrackChange> = changes
+            .into_iter()
+            .map(|(file, key, track_override)| TrackChange {
+                before: self.track_overrides.get(&file).and_then(|o| o.get(&key)).cloned(),
+                after: (!track_override.is_empty()).then_some(track_override),
+                file,
+                key,
+            })
+            .filter(|change| change.before != change.after)
+            .collect();
+        if changes.is_empty() {
+            return;
+        }
+        self.write_track_changes(&changes, false);
+        self.history.record(label, Command::Tracks(changes));
+    }
+
+    /// Setzt die Handänderungen auf den Stand vor (`undo`) bzw. nach den Änderungen.
+    fn write_track_changes(&mut self, changes: &[TrackChange], undo: bool) {
+        for change in changes {
+            let overrides = self.track_overrides.entry(change.file.clone()).or_default();
+            match if undo { &change.before } else { &change.after } {
+                Some(track_override) => overrides.insert(change.key.clone(), track_override.clone()),
+                None => overrides.remove(&change.key),
+            };
+        }
+        self.rebuild_tracks();
+    }
+
+    /// Schreibt einen Datenbankeintrag, merkt sich den vorherigen Inhalt für den Verlauf
+    /// und gleicht die betroffenen Tracks neu ab. Gibt zurück, ob der Eintrag schon vorhanden war.
+    pub fn save_database_row(&mut self, row: DatabaseRow) -> Result<bool> {
+        let conn = self.db_connection.as_ref().ok_or_else(|| anyhow!("Keine Datenbankverbindung"))?;
+        let before = load_database_row(conn, &row.index)?;
+        let existed = write_database_row(conn, &row)?;
+        let label = format!("Datenbank: '{}' {}", row.index, if existed { "aktualisiert" } else { "hinzugefügt" });
+        let index = row.index.clone();
+        self.history.record(label, Command::Database { before, after: row });
+        self.reenrich_index(&index);
+        Ok(existed)
+    }
+
+    /// Macht die letzte Änderung rückgängig. Gibt zurück, ob das gelungen ist.
+    pub fn undo(&mut self) -> bool {
+        let Some(entry) = self.history.pop_undo() else {
+            return false;
+        };
+        match self.apply_command(&entry.command, true) {
+            Ok(()) => {
+                info!("Rückgängig: {}", entry.label);
+                self.history.push_undone(entry);
+                true
+            }
             Err(e) => {
-                info!("Fehler beim Vorbereiten der Abfrage: {}", e);
-                return;
+                self.report(Diagnostic::error(
+                    DiagnosticKind::Database,
+                    format!("'{}' konnte nicht rückgängig gemacht werden: {:#}", entry.label, e),
+                ));
+                self.history.push_done(entry);
+                false
             }
+        }
+    }
+
+    /// Wiederholt die zuletzt rückgängig gemachte Änderung. Gibt zurück, ob das gelungen ist.
+    pub fn redo(&mut self) -> bool {
+        let Some(entry) = self.history.pop_redo() else {
+            return false;
         };
-    
-        // Iterate through all tracks and update artists from database
-        for (_filename, tracks) in self.tracks_per_file.iter_mut() {
-            for track in tracks.iter_mut() {
-                info!("Attempting to match track: '{}'", track.titel);
-                let result = stmt.query_row(params![&track.index], |row| {
-                    Ok((
-                        row.get::<_, String>(1)?, // Titel
-                        row.get::<_, String>(0)?, // Künstler
-                        row.get::<_, String>(2)?, // Labelcode
-                    ))
-                });
-    
-                match result {
-                    Ok((db_title, db_kuenstler, db_labelcode)) => {
-                        // **Daten mit DB-Werten überschreiben**
-                        track.titel = db_title;
-                        track.kuenstler = db_kuenstler;
-                        track.label_code = db_labelcode;
-    
-                        info!(
-                            "DB-Treffer: Titel='{}', Künstler='{}', Labelcode='{}'",
-                            track.titel, track.kuenstler, track.label_code
-                        );
+        match self.apply_command(&entry.command, false) {
+            Ok(()) => {
+                info!("Wiederholt: {}", entry.label);
+                self.history.push_done(entry);
+                true
+            }
+            Err(e) => {
+                self.report(Diagnostic::error(
+                    DiagnosticKind::Database,
+                    format!("'{}' konnte nicht wiederholt werden: {:#}", entry.label, e),
+                ));
+                self.history.push_undone(entry);
+                false
+            }
+        }
+    }
+
+    /// Stellt den Stand vor (`undo`) bzw. nach einer Änderung her, ohne den Verlauf zu ändern.
+    fn apply_command(&mut self, command: &Command, undo: bool) -> Result<()> {
+        match command {
+            Command::Tracks(changes) => self.write_track_changes(changes, undo),
+            Command::Files { before, after } => self.restore_file_list(if undo { before } else { after }),
+            Command::Database { before, after } => {
+                let conn = self.db_connection.as_ref().ok_or_else(|| anyhow!("Keine Datenbankverbindung"))?;
+                match (undo, before) {
+                    (true, Some(before)) => {
+                        write_database_row(conn, before)?;
                     }
-                    Err(_) => {
-                        info!(
-                            "Kein DB-Treffer für '{}', behalte ursprüngliche Werte.",
-                            track.index
-                        );
+                    // Neu angelegter Eintrag: wieder entfernen
+                    (true, None) => delete_database_row(conn, &after.index)?,
+                    (false, _) => {
+                        write_database_row(conn, after)?;
                     }
                 }
+                self.reenrich_index(&after.index);
             }
         }
+        Ok(())
+    }
+
+    /// Übernimmt eine Eingabe aus der Track-Übersicht als Handänderung. Entspricht sie
+    /// dem eingelesenen Stand, wird die Handänderung des Feldes verworfen.
+    pub fn set_track_field(&mut self, file: &str, key: &str, field: TrackField, text: &str) -> Result<()> {
+        let original = self
+            .original_track(file, key)
+            .cloned()
+            .ok_or_else(|| anyhow!("Track nicht gefunden"))?;
+        let text = text.trim();
+        // die Dauer wird gerundet angezeigt; der angezeigte eingelesene Wert gilt als unverändert
+        let duration = match field {
+            TrackField::Duration if original.duration.map(|d| self.format_duration(d.to_seconds())).as_deref() != Some(text) => {
+                let rate = original.duration.map_or(self.settings.fallback_frame_rate, |d| d.rate);
+                Some(self.parse_duration(text, rate).ok_or_else(|| {
+                    anyhow!("'{}' ist keine gültige Dauer (z. B. 75:50 für 75,5 Sekunden oder 00:01:15:12)", text)
+                })?)
+            }
+            _ => None,
+        };
+        let track_override = self.edited_override(file, key, |o| match field {
+            TrackField::Titel => o.titel = Some(text.to_string()),
+            TrackField::Kuenstler => o.kuenstler = Some(text.to_string()),
+            TrackField::LabelCode => o.label_code = Some(text.to_string()),
+            TrackField::Duration => o.duration = duration,
+        });
+        let label = format!("{} von '{}' geändert", field.label(), original.index);
+        self.change_tracks(label, vec![(file.to_string(), key.to_string(), track_override)]);
+        Ok(())
+    }
+
+    /// Setzt ein Feld auf den eingelesenen Stand zurück.
+    pub fn clear_track_field(&mut self, file: &str, key: &str, field: TrackField) {
+        if !self.track_overrides.get(file).is_some_and(|o| o.contains_key(key)) {
+            return;
+        }
+        let track_override = self.edited_override(file, key, |o| o.clear(field));
+        let label = format!("{} von '{}' zurückgesetzt", field.label(), self.track_index(file, key));
+        self.change_tracks(label, vec![(file.to_string(), key.to_string(), track_override)]);
+    }
+
+    pub fn cancel_parse_job(&mut self) {
+        if let Some(job) = self.parse_job.take() {
+            job.cancel();
+        }
+    }
+
+    /// Übernimmt das Ergebnis des Hintergrund-Durchlaufs, sobald es vorliegt.
+    pub fn poll_parse_job(&mut self) {
+        let Some(job) = self.parse_job.as_mut() else {
+            return;
+        };
+        match job.poll() {
+            Some(WorkerMessage::Finished(outcome)) => {
+                self.parse_job = None;
+                let ParseOutcome { mut ctx, parsed, unchanged, mut candidates } = *outcome;
+                // Frühere Meldungen zu neu eingelesenen Dateien sind überholt, alle
+                // anderen (z.B. ein Datenbankfehler während des Durchlaufs) bleiben
+                self.general_diagnostics
+                    .retain(|d| !parsed.iter().any(|(path, _)| d.file.as_deref() == Some(path.as_str())));
+                for (path, fingerprint) in parsed {
+                    let (diagnostics, rest) = ctx
+                        .diagnostics
+                        .into_iter()
+                        .partition(|d| d.file.as_deref() == Some(path.as_str()));
+                    ctx.diagnostics = rest;
+                    let entry = CacheEntry {
+                        fingerprint,
+                        config: self.parse_config(&path),
+                        tracks: ctx.tracks_per_file.remove(&path).unwrap_or_default(),
+                        detected_rate: ctx.detected_frame_rates.remove(&path),
+                        diagnostics,
+                        candidates: candidates.remove(&path).unwrap_or_default(),
+                    };
+                    self.parse_cache.insert(path, entry);
+                }
+                for (path, fingerprint) in unchanged {
+                    self.parse_cache.touch(&path, fingerprint);
+                }
+                for diagnostic in ctx.diagnostics {
+                    if !self.general_diagnostics.contains(&diagnostic) {
+                        self.general_diagnostics.push(diagnostic);
+                    }
+                }
+                self.rebuild_tracks();
+                if !self.pending_matches().is_empty() {
+                    self.show_match_dialog = true;
+                }
+                info!("Einlesen abgeschlossen.");
+            }
+            Some(WorkerMessage::Cancelled) => {
+                self.parse_job = None;
+                self.report(Diagnostic::new(
+                    Severity::Info,
+                    DiagnosticKind::File,
+                    "Einlesen abgebrochen, die bisherigen Ergebnisse bleiben erhalten.",
+                ));
+            }
+            Some(WorkerMessage::Progress(_)) | None => {}
+        }
+    }
+}
+
+/// Dateiname ohne Pfad, für Verlaufseinträge.
+fn file_label(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map_or(path.to_string(), |name| name.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enrich::RIGHTS_HOLDERS_SCHEMA;
+    use crate::model::TrackInfo;
+
+    fn app_with_track(index: &str, titel: &str) -> GemaLauncherApp {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(r#"CREATE TABLE my_table ("index" TEXT, titel TEXT, kuenstler TEXT, labelcode TEXT);"#)
+            .unwrap();
+        conn.execute_batch(RIGHTS_HOLDERS_SCHEMA).unwrap();
+        let mut track = TrackInfo {
+            key: TrackInfo::row_key(index, 1),
+            index: index.to_string(),
+            titel: titel.to_string(),
+            kuenstler: "aus datei".to_string(),
+            duration: None,
+            label_code: String::new(),
+            isrc: String::new(),
+            usages: Vec::new(),
+            versions: Vec::new(),
+            first_record_in: None,
+            last_record_out: None,
+            rights_holders: Vec::new(),
+            parsed: None,
+        };
+        track.remember_parsed();
+
+        let mut app = GemaLauncherApp {
+            db_connection: Some(conn),
+            filenames: vec!["liste.txt".to_string()],
+            ..GemaLauncherApp::default()
+        };
+        app.parse_cache.insert(
+            "liste.txt".to_string(),
+            CacheEntry {
+                fingerprint: None,
+                config: 0,
+                tracks: vec![track],
+                detected_rate: None,
+                diagnostics: Vec::new(),
+                candidates: Vec::new(),
+            },
+        );
+        app
+    }
+
+    fn titel(app: &GemaLauncherApp) -> &str {
+        &app.tracks_per_file["liste.txt"][0].titel
+    }
+
+    #[test]
+    fn undoing_a_new_database_entry_restores_parsed_values() {
+        let mut app = app_with_track("ANW1832_001", "aus datei");
+        let row = DatabaseRow {
+            index: "ANW1832_001".to_string(),
+            titel: "Aus der Datenbank".to_string(),
+            kuenstler: "DB".to_string(),
+            label_code: "LC 12345".to_string(),
+            rights_holders: Vec::new(),
+        };
+        assert!(!app.save_database_row(row).unwrap());
+        assert_eq!(titel(&app), "Aus der Datenbank");
+
+        assert!(app.undo());
+        assert_eq!(titel(&app), "aus datei");
+        assert_eq!(app.tracks_per_file["liste.txt"][0].kuenstler, "aus datei");
+        // nur neu abgeglichen, nicht neu eingelesen
+        assert!(app.parse_job.is_none());
+
+        assert!(app.redo());
+        assert_eq!(titel(&app), "Aus der Datenbank");
     }
 }
@@ -0,0 +1,498 @@
+use crate::diagnostic::{Diagnostic, DiagnosticKind};
+use crate::parser::{add_track_event, resolve_frame_rate, ParseContext, TrackEvent};
+use crate::timecode::Timecode;
+use anyhow::{bail, Result};
+use log::info;
+use regex::Regex;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::LazyLock;
+
+static LABEL_CODE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(?:LC|Label\s*code)[\s:#-]*0*(\d{4,5})\b").unwrap());
+static ISRC_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b([A-Z]{2}-?[A-Z0-9]{3}-?\d{2}-?\d{5})\b").unwrap());
+
+/// Größere Metadaten-Chunks werden übersprungen statt eingelesen.
+const MAX_METADATA_CHUNK: u64 = 16 * 1024 * 1024;
+
+/// In einer Audiodatei eingebettete Angaben. Leere Felder = nicht vorhanden.
+#[derive(Debug, Default, Clone)]
+pub struct AudioMetadata {
+    pub title: String,
+    pub artist: String,
+    pub composer: String,
+    pub publisher: String,
+    pub isrc: String,
+    pub label_code: String,
+}
+
+impl AudioMetadata {
+    fn is_empty(&self) -> bool {
+        self.title.is_empty() && self.artist.is_empty() && self.composer.is_empty() && self.publisher.is_empty()
+    }
+
+    /// Setzt ein Feld nur, wenn es noch leer ist (erste Quelle gewinnt).
+    fn fill(field: &mut String, value: &str) {
+        let value = value.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+        if field.is_empty() && !value.is_empty() {
+            *field = value.to_string();
+        }
+    }
+
+    /// Sucht Labelcode und ISRC in Freitext (bext-Beschreibung, Kommentare).
+    fn scan_free_text(&mut self, text: &str) {
+        if self.label_code.is_empty() {
+            if let Some(caps) = LABEL_CODE_RE.captures(text) {
+                self.label_code = caps[1].to_string();
+            }
+        }
+        if self.isrc.is_empty() {
+            if let Some(caps) = ISRC_RE.captures(text) {
+                self.isrc = caps[1].replace('-', "");
+            }
+        }
+    }
+}
+
+/// WAV/BWF: liest `bext`, `iXML` und `LIST/INFO` sowie die echte Laufzeit aus `fmt `/`data`.
+/// Es werden nur Chunk-Header und Metadaten-Chunks gelesen, die Audiodaten übersprungen.
+pub fn parse_wav_file(ctx: &mut ParseContext, path: &str) -> Result<()> {
+    let mut file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut header = [0u8; 12];
+    if file.read_exact(&mut header).is_err()
+        || (&header[0..4] != b"RIFF" && &header[0..4] != b"RF64")
+        || &header[8..12] != b"WAVE"
+    {
+        bail!("Keine gültige WAV-Datei");
+    }
+
+    let mut meta = AudioMetadata::default();
+    let mut byte_rate: Option<u32> = None;
+    let mut data_size: Option<u64> = None;
+    let mut ds64_data_size: Option<u64> = None;
+
+    let mut pos: u64 = 12;
+    while pos + 8 <= file_len {
+        file.seek(SeekFrom::Start(pos))?;
+        let mut chunk_header = [0u8; 8];
+        file.read_exact(&mut chunk_header)?;
+        let id: [u8; 4] = chunk_header[0..4].try_into()?;
+        let mut size = u32::from_le_bytes(chunk_header[4..8].try_into()?) as u64;
+        let available = file_len - pos - 8;
+
+        // Bei RF64 steht die Datenlänge im ds64-Chunk
+        if &id == b"data" && size == u32::MAX as u64 {
+            size = ds64_data_size.unwrap_or(available);
+        }
+
+        match &id {
+            b"data" => data_size = Some(size),
+            b"fmt " | b"ds64" | b"bext" | b"iXML" | b"LIST" if size <= MAX_METADATA_CHUNK => {
+                let mut body = vec![0; size.min(available) as usize];
+                file.read_exact(&mut body)?;
+                match &id {
+                    b"fmt " if body.len() >= 12 => {
+                        byte_rate = Some(u32::from_le_bytes(body[8..12].try_into()?));
+                    }
+                    b"ds64" if body.len() >= 16 => {
+                        ds64_data_size = Some(u64::from_le_bytes(body[8..16].try_into()?));
+                    }
+                    b"bext" => read_bext(&body, &mut meta),
+                    b"iXML" => read_ixml(&String::from_utf8_lossy(&body), &mut meta),
+                    b"LIST" if body.len() >= 4 && &body[0..4] == b"INFO" => read_riff_info(&body[4..], &mut meta),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+
+        pos += 8 + size + (size & 1);
+    }
+
+    let (Some(byte_rate), Some(data_size)) = (byte_rate, data_size) else {
+        bail!("WAV-Datei ohne fmt- oder data-Chunk");
+    };
+    if byte_rate == 0 {
+        bail!("WAV-Datei mit ungültiger Byterate");
+    }
+
+    let rate = resolve_frame_rate(ctx, path, None);
+    let duration = Timecode::from_rational_seconds(data_size as i64, byte_rate as i64, rate);
+    add_audio_track(ctx, path, meta, duration);
+    Ok(())
+}
+
+/// MP3: liest ID3v2-Frames (TIT2, TPE1, TCOM, TPUB, TSRC, TXXX, COMM) und
+/// bestimmt die Laufzeit durch Zählen der MPEG-Frames (auch bei VBR exakt).
+pub fn parse_mp3_file(ctx: &mut ParseContext, path: &str) -> Result<()> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut meta = AudioMetadata::default();
+
+    let mut audio_start = 0;
+    let mut header = [0u8; 10];
+    if read_or_eof(&mut reader, &mut header)? && &header[0..3] == b"ID3" {
+        let (version, flags) = (header[3], header[5]);
+        let tag_size = syncsafe(&header[6..10]) as u64;
+        let mut tag = Vec::new();
+        (&mut reader).take(tag_size).read_to_end(&mut tag)?;
+        if let Err(e) = read_id3v2(&tag, version, flags, &mut meta) {
+            ctx.report(
+                Diagnostic::warning(DiagnosticKind::UnsupportedFormat, format!("ID3v2-Tag nicht gelesen: {}", e))
+                    .file(path)
+                    .suggest("Metadaten mit einem aktuellen Tag-Editor als ID3v2.3 oder ID3v2.4 speichern"),
+            );
+        }
+        // ID3v2.4 kann hinter dem Tag eine 10-Byte-Fußzeile haben
+        let footer = if version == 4 && flags & 0x10 != 0 { 10 } else { 0 };
+        audio_start = 10 + tag_size + footer;
+    }
+    reader.seek(SeekFrom::Start(audio_start))?;
+
+    let Some((samples, sample_rate)) = count_mpeg_samples(&mut reader)? else {
+        bail!("Keine MPEG-Audioframes gefunden");
+    };
+
+    let rate = resolve_frame_rate(ctx, path, None);
+    let duration = Timecode::from_rational_seconds(samples as i64, sample_rate as i64, rate);
+    add_audio_track(ctx, path, meta, duration);
+    Ok(())
+}
+
+/// Liest `buf` voll; `false` am Dateiende.
+fn read_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn add_audio_track(ctx: &mut ParseContext, path: &str, meta: AudioMetadata, duration: Timecode) {
+    let clip_name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    if meta.is_empty() {
+        info!("Keine Metadaten in '{}', verwende Dateinamen.", path);
+    } else {
+        info!("Metadaten aus '{}': {:?}", path, meta);
+    }
+
+    let mut event = TrackEvent::new(clip_name, duration);
+    event.metadata = Some(meta);
+    add_track_event(ctx, path, event);
+}
+
+/// Broadcast Wave `bext`: Description (256), Originator (32), ... danach Coding History.
+fn read_bext(body: &[u8], meta: &mut AudioMetadata) {
+    let text = |range: std::ops::Range<usize>| {
+        body.get(range)
+            .map(|b| String::from_utf8_lossy(b).trim_end_matches('\0').trim().to_string())
+            .unwrap_or_default()
+    };
+    let description = text(0..256);
+    let originator = text(256..288);
+
+    meta.scan_free_text(&description);
+    AudioMetadata::fill(&mut meta.publisher, &originator);
+    if body.len() > 602 {
+        meta.scan_free_text(&String::from_utf8_lossy(&body[602..]));
+    }
+}
+
+/// iXML: Bibliotheks-Metadaten (z.B. ASWG oder USER-Felder) per Elementname.
+fn read_ixml(xml: &str, meta: &mut AudioMetadata) {
+    let xml = xml.trim_end_matches('\0');
+    let Ok(doc) = roxmltree::Document::parse(xml) else {
+        meta.scan_free_text(xml);
+        return;
+    };
+
+    for node in doc.descendants().filter(|n| n.is_element()) {
+        let Some(text) = node.text().map(|t| t.trim()).filter(|t| !t.is_empty()) else {
+            continue;
+        };
+        match node.tag_name().name().to_uppercase().as_str() {
+            "SONGTITLE" | "TRACK_TITLE" | "TRACKTITLE" | "TITLE" => AudioMetadata::fill(&mut meta.title, text),
+            "ARTIST" => AudioMetadata::fill(&mut meta.artist, text),
+            "COMPOSER" => AudioMetadata::fill(&mut meta.composer, text),
+            "PUBLISHER" | "MUSICPUBLISHER" => AudioMetadata::fill(&mut meta.publisher, text),
+            "ISRC" | "ISRCID" => AudioMetadata::fill(&mut meta.isrc, text),
+            "LABELCODE" | "LABEL_CODE" => AudioMetadata::fill(&mut meta.label_code, text.trim_start_matches("LC").trim()),
+            "NOTE" | "DESCRIPTION" | "COMMENT" => meta.scan_free_text(text),
+            _ => {}
+        }
+    }
+}
+
+/// RIFF LIST/INFO: INAM (Titel), IART (Künstler), ICMT (Kommentar), ICOP (Copyright) ...
+fn read_riff_info(mut body: &[u8], meta: &mut AudioMetadata) {
+    while body.len() >= 8 {
+        let id = &body[0..4];
+        let size = u32::from_le_bytes([body[4], body[5], body[6], body[7]]) as usize;
+        let value = body.get(8..8 + size).unwrap_or(&body[8..]);
+        let text = String::from_utf8_lossy(value).trim_end_matches('\0').trim().to_string();
+
+        match id {
+            b"INAM" => AudioMetadata::fill(&mut meta.title, &text),
+            b"IART" => AudioMetadata::fill(&mut meta.artist, &text),
+            b"IMUS" => AudioMetadata::fill(&mut meta.composer, &text),
+            b"IPUB" => AudioMetadata::fill(&mut meta.publisher, &text),
+            b"ICMT" | b"ICOP" | b"ISRC" => meta.scan_free_text(&text),
+            _ => {}
+        }
+
+        let advance = 8 + size + (size & 1);
+        if advance > body.len() {
+            break;
+        }
+        body = &body[advance..];
+    }
+}
+
+/// Liest die Frames eines ID3v2-Tags (ohne 10-Byte-Kopf). Erweiterter Header und
+/// Unsynchronisation werden berücksichtigt, komprimierte/verschlüsselte Frames übersprungen.
+fn read_id3v2(tag: &[u8], version: u8, flags: u8, meta: &mut AudioMetadata) -> Result<()> {
+    let unsynchronised = flags & 0x80 != 0;
+    if !(2..=4).contains(&version) {
+        bail!("ID3v2.{} wird nicht unterstützt", version);
+    }
+    if version == 2 && flags & 0x40 != 0 {
+        bail!("komprimiertes ID3v2.2-Tag wird nicht unterstützt");
+    }
+
+    // Bis v2.3 betrifft die Unsynchronisation das ganze Tag, ab v2.4 die einzelnen Frames
+    let tag: Cow<[u8]> = if unsynchronised && version < 4 { Cow::Owned(resync(tag)) } else { Cow::Borrowed(tag) };
+
+    let mut pos = 0;
+    if version >= 3 && flags & 0x40 != 0 {
+        let Some(size_bytes) = tag.get(0..4) else {
+            bail!("erweiterter Header abgeschnitten");
+        };
+        // v2.3: Größe ohne die 4 Größenbytes, v2.4: syncsafe inklusive
+        pos = match version {
+            3 => 4 + u32::from_be_bytes(size_bytes.try_into()?) as usize,
+            _ => syncsafe(size_bytes) as usize,
+        };
+        if pos > tag.len() {
+            bail!("erweiterter Header größer als das Tag");
+        }
+    }
+
+    // ID3v2.2 hat 3-stellige Frame-IDs und 3-Byte-Größen
+    let (id_len, header_len) = if version == 2 { (3, 6) } else { (4, 10) };
+
+    while pos + header_len <= tag.len() {
+        let id = String::from_utf8_lossy(&tag[pos..pos + id_len]).to_string();
+        if id.starts_with('\0') {
+            break; // Padding
+        }
+        let size = match version {
+            2 => u32::from_be_bytes([0, tag[pos + 3], tag[pos + 4], tag[pos + 5]]) as usize,
+            4 => syncsafe(&tag[pos + 4..pos + 8]) as usize,
+            _ => u32::from_be_bytes(tag[pos + 4..pos + 8].try_into().unwrap_or([0; 4])) as usize,
+        };
+        let format_flags = if version == 2 { 0 } else { tag[pos + 9] };
+        let body_start = pos + header_len;
+        let Some(raw) = tag.get(body_start..body_start + size) else {
+            break;
+        };
+        pos = body_start + size;
+
+        let (compressed, encrypted) = match version {
+            3 => (format_flags & 0x80 != 0, format_flags & 0x40 != 0),
+            4 => (format_flags & 0x08 != 0, format_flags & 0x04 != 0),
+            _ => (false, false),
+        };
+        if compressed || encrypted {
+            continue;
+        }
+        let mut body: Cow<[u8]> = Cow::Borrowed(raw);
+        if version == 4 {
+            // Datenlängenangabe (4 Byte syncsafe) vor dem Inhalt
+            if format_flags & 0x01 != 0 {
+                body = Cow::Borrowed(raw.get(4..).unwrap_or_default());
+            }
+            if unsynchronised || format_flags & 0x02 != 0 {
+                body = Cow::Owned(resync(&body));
+            }
+        }
+        let body = &body[..];
+
+        match id.as_str() {
+            "TIT2" | "TT2" => AudioMetadata::fill(&mut meta.title, &id3_text(body)),
+            "TPE1" | "TP1" => AudioMetadata::fill(&mut meta.artist, &id3_text(body)),
+            "TCOM" | "TCM" => AudioMetadata::fill(&mut meta.composer, &id3_text(body)),
+            "TPUB" | "TPB" => {
+                let publisher = id3_text(body);
+                meta.scan_free_text(&publisher);
+                AudioMetadata::fill(&mut meta.publisher, &publisher);
+            }
+            "TSRC" | "TRC" => AudioMetadata::fill(&mut meta.isrc, &id3_text(body).replace('-', "")),
+            "TXXX" | "TXX" => {
+                // Benutzerdefiniert: "Beschreibung\0Wert"
+                let text = id3_text(body);
+                if let Some((description, value)) = text.split_once('\0') {
+                    let description = description.to_uppercase().replace([' ', '_'], "");
+                    if description == "LABELCODE" || description == "LC" {
+                        AudioMetadata::fill(&mut meta.label_code, value.trim_start_matches("LC").trim());
+                    } else if description == "ISRC" {
+                        AudioMetadata::fill(&mut meta.isrc, value);
+                    }
+                }
+            }
+            "COMM" | "COM" if body.len() > 4 => {
+                // Kommentar: Kodierung, Sprache (3), Kurzbeschreibung\0Text
+                let mut comment = vec![body[0]];
+                comment.extend_from_slice(&body[4..]);
+                meta.scan_free_text(&id3_text(&comment));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Macht die ID3-Unsynchronisation rückgängig: ein auf 0xFF folgendes 0x00 entfällt.
+fn resync(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut previous = 0;
+    for &byte in data {
+        if !(previous == 0xFF && byte == 0x00) {
+            out.push(byte);
+        }
+        previous = byte;
+    }
+    out
+}
+
+/// Textframe: erstes Byte = Kodierung (0 Latin-1, 1 UTF-16 mit BOM, 2 UTF-16BE, 3 UTF-8).
+fn id3_text(body: &[u8]) -> String {
+    let Some((&encoding, text)) = body.split_first() else {
+        return String::new();
+    };
+    let decoded = match encoding {
+        0 => encoding_rs::WINDOWS_1252.decode_without_bom_handling(text).0.into_owned(),
+        1 => encoding_rs::UTF_16LE.decode(text).0.into_owned(),
+        2 => encoding_rs::UTF_16BE.decode_without_bom_handling(text).0.into_owned(),
+        _ => String::from_utf8_lossy(text).into_owned(),
+    };
+    decoded.trim_end_matches('\0').to_string()
+}
+
+fn syncsafe(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |acc, &b| (acc << 7) | (b & 0x7F) as u32)
+}
+
+/// Zählt alle MPEG-Audioframes und liefert (Samples, Samplerate). Gelesen werden nur
+/// die Frame-Header, die Audiodaten werden übersprungen.
+fn count_mpeg_samples(reader: &mut BufReader<File>) -> io::Result<Option<(u64, u32)>> {
+    const BITRATES_V1_L3: [u32; 16] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 0];
+    const BITRATES_V1_L2: [u32; 16] = [0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 0];
+    const BITRATES_V1_L1: [u32; 16] = [0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448, 0];
+    const BITRATES_V2_L1: [u32; 16] = [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256, 0];
+    const BITRATES_V2_L23: [u32; 16] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0];
+    const SAMPLE_RATES: [u32; 3] = [44100, 48000, 32000];
+    /// Bereich nach dem Frame-Header, in dem ein Xing/Info-Header stehen kann
+    const VBR_HEADER_SPAN: usize = 48;
+
+    let mut total_samples: u64 = 0;
+    let mut stream_rate: Option<u32> = None;
+
+    let mut window = [0u8; 4];
+    let mut more = read_or_eof(reader, &mut window)?;
+    while more {
+        if &window[0..3] == b"TAG" {
+            break; // ID3v1 am Dateiende
+        }
+        let header = u32::from_be_bytes(window);
+
+        let version_bits = (header >> 19) & 0b11; // 0 = 2.5, 2 = 2, 3 = 1
+        let layer_bits = (header >> 17) & 0b11; // 1 = III, 2 = II, 3 = I
+        let bitrate_index = ((header >> 12) & 0xF) as usize;
+        let rate_index = ((header >> 10) & 0b11) as usize;
+        let padding = (header >> 9) & 1;
+        let valid = header & 0xFFE0_0000 == 0xFFE0_0000
+            && version_bits != 1
+            && layer_bits != 0
+            && rate_index != 3
+            && bitrate_index != 0
+            && bitrate_index != 15;
+
+        let frame = valid.then(|| {
+            let mpeg1 = version_bits == 3;
+            let sample_rate = SAMPLE_RATES[rate_index] >> match version_bits {
+                3 => 0,
+                2 => 1,
+                _ => 2,
+            };
+            let bitrate = 1000
+                * match (mpeg1, layer_bits) {
+                    (true, 3) => BITRATES_V1_L1[bitrate_index],
+                    (true, 2) => BITRATES_V1_L2[bitrate_index],
+                    (true, _) => BITRATES_V1_L3[bitrate_index],
+                    (false, 3) => BITRATES_V2_L1[bitrate_index],
+                    (false, _) => BITRATES_V2_L23[bitrate_index],
+                };
+            let (samples, frame_len) = match layer_bits {
+                3 => (384, (12 * bitrate / sample_rate + padding) * 4),
+                2 => (1152, 144 * bitrate / sample_rate + padding),
+                _ if mpeg1 => (1152, 144 * bitrate / sample_rate + padding),
+                _ => (576, 72 * bitrate / sample_rate + padding),
+            };
+            (samples, sample_rate, frame_len as usize)
+        });
+
+        // Alle Frames eines Streams haben dieselbe Samplerate; Abweichung = Fehlsynchronisation
+        let Some((samples, sample_rate, frame_len)) =
+            frame.filter(|&(_, rate, len)| len >= 4 && stream_rate.is_none_or(|r| r == rate))
+        else {
+            window.rotate_left(1);
+            more = read_or_eof(reader, &mut window[3..])?;
+            continue;
+        };
+
+        // Der Xing/Info-Header eines VBR-Streams ist ein Frame ohne Audio
+        let mut skip = frame_len - 4;
+        let mut is_vbr_header = false;
+        if stream_rate.is_none() {
+            let mut start = vec![0; skip.min(VBR_HEADER_SPAN)];
+            let read = reader.read(&mut start)?;
+            skip -= read;
+            is_vbr_header = start[..read].windows(4).any(|w| w == b"Xing" || w == b"Info");
+        }
+        stream_rate = Some(sample_rate);
+        if !is_vbr_header {
+            total_samples += samples as u64;
+        }
+        reader.seek_relative(skip as i64)?;
+        more = read_or_eof(reader, &mut window)?;
+    }
+
+    Ok(stream_rate.map(|rate| (total_samples, rate)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_code_and_isrc_are_found_in_free_text() {
+        let mut meta = AudioMetadata::default();
+        meta.scan_free_text("Production music, LC 02817, ISRC DE-A12-24-00123");
+        assert_eq!(meta.label_code, "2817");
+        assert_eq!(meta.isrc, "DEA122400123");
+    }
+
+    #[test]
+    fn first_source_wins() {
+        let mut meta = AudioMetadata::default();
+        meta.scan_free_text("Labelcode: 12345");
+        meta.scan_free_text("LC 54321");
+        assert_eq!(meta.label_code, "12345");
+    }
+}
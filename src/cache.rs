@@ -0,0 +1,150 @@
+use crate::diagnostic::Diagnostic;
+use crate::enrich::MatchCandidates;
+use crate::model::TrackInfo;
+use crate::timecode::FrameRate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// FNV-1a (64 Bit). Anders als `DefaultHasher` über Programmversionen stabil;
+/// die Hashes werden in `.gemaproj`-Dateien gespeichert.
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl StableHasher {
+    /// Text mit Längenpräfix, damit ("ab", "c") und ("a", "bc") verschieden hashen.
+    pub fn write_str(&mut self, text: &str) {
+        self.write(&(text.len() as u64).to_le_bytes());
+        self.write(text.as_bytes());
+    }
+}
+
+/// Stand einer Eingabedatei: Änderungszeit und Hash des Inhalts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFingerprint {
+    pub modified: Option<SystemTime>,
+    pub content_hash: u64,
+}
+
+/// Liest die Datei in Blöcken und bildet ihren Fingerabdruck.
+/// Bei .fcpxmld-Bundles zählt die enthaltene Info.fcpxml.
+pub fn fingerprint(path: &str) -> io::Result<FileFingerprint> {
+    let path = Path::new(path);
+    let content_path = if path.is_dir() { path.join("Info.fcpxml") } else { path.to_path_buf() };
+    let mut file = File::open(&content_path)?;
+
+    let mut hasher = StableHasher::default();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.write(&buffer[..read]);
+    }
+    Ok(FileFingerprint {
+        modified: modified(&content_path.to_string_lossy()),
+        content_hash: hasher.finish(),
+    })
+}
+
+pub fn modified(path: &str) -> Option<SystemTime> {
+    let path = Path::new(path);
+    let content_path = if path.is_dir() { path.join("Info.fcpxml") } else { path.to_path_buf() };
+    fs::metadata(content_path).and_then(|m| m.modified()).ok()
+}
+
+/// Ergebnis einer eingelesenen (und angereicherten) Datei.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub fingerprint: Option<FileFingerprint>,
+    /// Hash der Einstellungen, Labelcodes und Datenbank, mit denen die Datei gelesen wurde.
+    pub config: u64,
+    pub tracks: Vec<TrackInfo>,
+    pub detected_rate: Option<FrameRate>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Datenbank-Vorschläge für Tracks ohne sicheren Treffer.
+    pub candidates: Vec<MatchCandidates>,
+}
+
+/// Was mit einer Datei beim nächsten Durchlauf passieren muss.
+pub enum CacheState {
+    /// Änderungszeit und Einstellungen unverändert
+    Current,
+    /// Änderungszeit neu, Inhalt vielleicht gleich: Hash vergleichen
+    Modified(u64),
+    Missing,
+}
+
+/// Ergebnisse je Datei, damit nur neue oder geänderte Dateien neu eingelesen werden.
+#[derive(Default)]
+pub struct ParseCache {
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl ParseCache {
+    pub fn state(&self, path: &str, config: u64) -> CacheState {
+        match self.entries.get(path) {
+            Some(entry) if entry.config == config => match entry.fingerprint {
+                Some(fp) if fp.modified.is_some() && fp.modified == modified(path) => CacheState::Current,
+                Some(fp) => CacheState::Modified(fp.content_hash),
+                None => CacheState::Missing,
+            },
+            _ => CacheState::Missing,
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Option<&CacheEntry> {
+        self.entries.get(path)
+    }
+
+    pub fn get_mut(&mut self, path: &str) -> Option<&mut CacheEntry> {
+        self.entries.get_mut(path)
+    }
+
+    pub fn insert(&mut self, path: String, entry: CacheEntry) {
+        self.entries.insert(path, entry);
+    }
+
+    /// Inhalt unverändert (nur angefasst): neue Änderungszeit übernehmen.
+    pub fn touch(&mut self, path: &str, fingerprint: FileFingerprint) {
+        if let Some(entry) = self.entries.get_mut(path) {
+            entry.fingerprint = Some(fingerprint);
+        }
+    }
+
+    /// Entfernt Einträge von Dateien, die nicht mehr in der Liste sind.
+    pub fn retain_files(&mut self, filenames: &[String]) {
+        self.entries.retain(|path, _| filenames.contains(path));
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn entries_mut(&mut self) -> impl Iterator<Item = (&String, &mut CacheEntry)> {
+        self.entries.iter_mut()
+    }
+}
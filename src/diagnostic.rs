@@ -0,0 +1,151 @@
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Schweregrad einer Meldung.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 3] = [Severity::Error, Severity::Warning, Severity::Info];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Info => "Hinweis",
+            Severity::Warning => "Warnung",
+            Severity::Error => "Fehler",
+        }
+    }
+}
+
+/// Art des Problems, damit Meldungen gefiltert und gezählt werden können.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DiagnosticKind {
+    /// Datei nicht lesbar oder kein gültiges Format
+    File,
+    UnsupportedFormat,
+    MissingColumn,
+    /// Clipname ohne erkennbaren Index
+    ClipName,
+    /// Zeile mit zu wenigen Spalten o.ä.
+    LineFormat,
+    InvalidTimecode,
+    Encoding,
+    LabelCodeConflict,
+    /// Keine passenden Spuren/Clips gefunden
+    NoMusic,
+    Config,
+    Database,
+    Export,
+}
+
+impl DiagnosticKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DiagnosticKind::File => "Datei",
+            DiagnosticKind::UnsupportedFormat => "Format",
+            DiagnosticKind::MissingColumn => "Spalte fehlt",
+            DiagnosticKind::ClipName => "Clipname",
+            DiagnosticKind::LineFormat => "Zeilenformat",
+            DiagnosticKind::InvalidTimecode => "Timecode",
+            DiagnosticKind::Encoding => "Kodierung",
+            DiagnosticKind::LabelCodeConflict => "Labelcode",
+            DiagnosticKind::NoMusic => "Keine Musik",
+            DiagnosticKind::Config => "Einstellungen",
+            DiagnosticKind::Database => "Datenbank",
+            DiagnosticKind::Export => "Export",
+        }
+    }
+}
+
+/// Eine Meldung aus Einlesen, Anreicherung oder Export, mit Fundstelle.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    pub message: String,
+    /// Eingabedatei (bzw. Exportdatei), auf die sich die Meldung bezieht.
+    pub file: Option<String>,
+    /// Zeilennummer (1-basiert) und Inhalt der betroffenen Zeile.
+    pub line: Option<usize>,
+    pub raw_line: Option<String>,
+    pub suggestion: Option<String>,
+    /// Index des betroffenen Tracks, für den Sprung in die Track-Übersicht.
+    pub track_index: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, kind: DiagnosticKind, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            kind,
+            message: message.into(),
+            file: None,
+            line: None,
+            raw_line: None,
+            suggestion: None,
+            track_index: None,
+        }
+    }
+
+    pub fn error(kind: DiagnosticKind, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, kind, message)
+    }
+
+    pub fn warning(kind: DiagnosticKind, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, kind, message)
+    }
+
+    pub fn file(mut self, file: &str) -> Self {
+        self.file = Some(file.to_string());
+        self
+    }
+
+    pub fn line(mut self, number: usize, raw: &str) -> Self {
+        self.line = Some(number);
+        self.raw_line = Some(raw.trim_end().to_string());
+        self
+    }
+
+    pub fn suggest(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    pub fn track(mut self, index: &str) -> Self {
+        self.track_index = Some(index.to_string());
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}", file)?;
+            if let Some(line) = self.line {
+                write!(f, ":{}", line)?;
+            }
+            write!(f, ": ")?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Erlaubt Parsern, eine fertige Meldung als Fehler zurückzugeben (`Err(diagnostic.into())`).
+impl std::error::Error for Diagnostic {}
+
+/// Protokolliert eine Meldung und nimmt sie auf; identische Meldungen nur einmal.
+pub fn push_diagnostic(diagnostics: &mut Vec<Diagnostic>, diagnostic: Diagnostic) {
+    match diagnostic.severity {
+        Severity::Error => error!("{}", diagnostic),
+        Severity::Warning => warn!("{}", diagnostic),
+        Severity::Info => info!("{}", diagnostic),
+    }
+    if !diagnostics.contains(&diagnostic) {
+        diagnostics.push(diagnostic);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::diagnostic::{Diagnostic, DiagnosticKind};
+use crate::parser::{add_track_event, frame_rate_for_file, read_text_file, resolve_frame_rate, ParseContext, TrackEvent};
+use crate::textfile::read_text_head;
+use crate::timecode::{detect_frame_rate, FrameRate, Timecode};
+use anyhow::Result;
+use log::info;
+use regex::Regex;
+use std::sync::LazyLock;
+
+static EVENT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<num>\d{3,6})\s+(?P<reel>\S+)\s+(?P<channels>\S+)\s+(?P<trans>C|D|W\d{3}|K\s?[BO]?)\s+(?:\d{3}\s+)?(?P<src_in>\d{2}:\d{2}:\d{2}[:;.]\d{2})\s+(?P<src_out>\d{2}:\d{2}:\d{2}[:;.]\d{2})\s+(?P<rec_in>\d{2}:\d{2}:\d{2}[:;.]\d{2})\s+(?P<rec_out>\d{2}:\d{2}:\d{2}[:;.]\d{2})",
+    )
+    .unwrap()
+});
+
+/// Ein Ereignis einer CMX3600-EDL, solange die Kommentarzeilen noch eingesammelt werden.
+struct EdlEvent {
+    line: String,
+    reel: String,
+    channels: Vec<u32>,
+    record_in: Timecode,
+    record_out: Timecode,
+    clip_name: Option<String>,
+    source_file: Option<String>,
+}
+
+/// Erkennt EDLs, die mit der Endung .txt exportiert wurden ("TITLE:" bzw. "FCM:" im Kopf).
+pub fn looks_like_edl(path: &str) -> bool {
+    let Some(head) = read_text_head(path) else {
+        return false;
+    };
+    head.to_uppercase()
+        .lines()
+        .map(|l| l.trim())
+        .find(|l| !l.is_empty())
+        .is_some_and(|l| l.starts_with("TITLE:") || l.starts_with("FCM:"))
+}
+
+/// Liest eine Avid/Resolve CMX3600-EDL ein.
+/// Die Dauer ergibt sich aus Record-In/-Out, der Clipname aus "* FROM CLIP NAME:".
+/// Video-Ereignisse und Audiokanäle außerhalb des Kanalfilters werden übersprungen.
+pub fn parse_edl_file(ctx: &mut ParseContext, path: &str) -> Result<()> {
+    let content = read_text_file(ctx, path)?;
+    parse_edl_text(ctx, path, &content)
+}
+
+fn parse_edl_text(ctx: &mut ParseContext, path: &str, content: &str) -> Result<()> {
+    let lines: Vec<&str> = content.lines().collect();
+
+    let header: String = lines
+        .iter()
+        .take_while(|l| !EVENT_RE.is_match(l))
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n");
+    // "FCM: DROP FRAME" ohne Bildraten-Angabe gibt es nur bei 29,97 DF
+    let drop_frame = header.to_uppercase().contains("FCM: DROP FRAME");
+    let rate = if drop_frame && detect_frame_rate(&header).is_none() {
+        resolve_frame_rate(ctx, path, Some(FrameRate::FPS_29_97_DF))
+    } else {
+        let rate = frame_rate_for_file(ctx, path, &header, lines.iter().copied());
+        if drop_frame { rate.with_drop_frame(true) } else { rate }
+    };
+
+    let channel_filter = parse_channel_filter(&ctx.settings.edl_audio_channels);
+    let mut current: Option<EdlEvent> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if let Some(caps) = EVENT_RE.captures(trimmed) {
+            if let Some(event) = current.take() {
+                finish_event(ctx, path, event, &channel_filter);
+            }
+
+            let (Some(record_in), Some(record_out)) = (
+                Timecode::parse(&caps["rec_in"], rate),
+                Timecode::parse(&caps["rec_out"], rate),
+            ) else {
+                ctx.report(
+                    Diagnostic::error(DiagnosticKind::InvalidTimecode, "Ungültiger Record-Timecode in EDL-Ereignis")
+                        .file(path)
+                        .line(i + 1, line)
+                        .suggest("Bildrate der EDL prüfen (bei Drop-Frame \"FCM: DROP FRAME\" im Kopf)"),
+                );
+                continue;
+            };
+
+            current = Some(EdlEvent {
+                line: trimmed.to_string(),
+                reel: caps["reel"].to_string(),
+                channels: parse_channels(&caps["channels"]),
+                record_in,
+                record_out,
+                clip_name: None,
+                source_file: None,
+            });
+            continue;
+        }
+
+        let Some(event) = current.as_mut() else {
+            continue;
+        };
+
+        let upper = trimmed.to_uppercase();
+        if let Some(name) = comment_value(trimmed, "FROM CLIP NAME:") {
+            event.clip_name.get_or_insert(name);
+        } else if let Some(name) = comment_value(trimmed, "TO CLIP NAME:") {
+            // Bei Blenden gehört der Ziel-Clip zum eingehenden Ereignis
+            event.clip_name = Some(name);
+        } else if let Some(name) = comment_value(trimmed, "SOURCE FILE:") {
+            event.source_file = Some(name);
+        } else if let Some(rest) = upper.strip_prefix("AUD") {
+            // Erweiterte Audiokanäle, z.B. "AUD  3    4"
+            event.channels.extend(rest.split_whitespace().filter_map(|c| c.parse::<u32>().ok()));
+        }
+    }
+
+    if let Some(event) = current.take() {
+        finish_event(ctx, path, event, &channel_filter);
+    }
+
+    Ok(())
+}
+
+fn finish_event(ctx: &mut ParseContext, path: &str, event: EdlEvent, channel_filter: &[u32]) {
+    if event.channels.is_empty() {
+        info!("EDL: Video-Ereignis übersprungen: {}", event.line);
+        return;
+    }
+    if !channel_filter.is_empty() && !event.channels.iter().any(|c| channel_filter.contains(c)) {
+        info!("EDL: Audiokanal {:?} herausgefiltert: {}", event.channels, event.line);
+        return;
+    }
+
+    let duration = event.record_out - event.record_in;
+    if duration.frames <= 0 {
+        // Ausgehende Seite einer Blende oder leeres Ereignis
+        return;
+    }
+
+    let clip_name = event
+        .clip_name
+        .or(event.source_file)
+        .unwrap_or(event.reel);
+
+    add_track_event(ctx, path, TrackEvent::new(clip_name, duration).at(Some(event.record_in)));
+}
+
+/// Übersetzt das Kanalfeld ("V", "A", "A2", "AA", "B", "A3", "AA/V", "NONE")
+/// in die Liste der belegten Audiokanäle. Leer = reines Video-Ereignis.
+fn parse_channels(field: &str) -> Vec<u32> {
+    let mut channels = Vec::new();
+    for part in field.to_uppercase().split('/') {
+        match part {
+            "A" | "B" => channels.push(1),
+            "AA" => channels.extend([1, 2]),
+            p if p.starts_with('A') => {
+                if let Ok(n) = p[1..].parse::<u32>() {
+                    channels.push(n);
+                }
+            }
+            _ => {}
+        }
+    }
+    channels
+}
+
+/// Liest "1-4, 7" als Kanalliste. Leerer Text = alle Kanäle.
+pub fn parse_channel_filter(text: &str) -> Vec<u32> {
+    let mut channels = Vec::new();
+    for part in text.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let part = part.trim_start_matches(['A', 'a']);
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let from = from.trim().trim_start_matches(['A', 'a']).parse::<u32>();
+                let to = to.trim().trim_start_matches(['A', 'a']).parse::<u32>();
+                if let (Ok(from), Ok(to)) = (from, to) {
+                    channels.extend(from..=to);
+                }
+            }
+            None => {
+                if let Ok(n) = part.parse::<u32>() {
+                    channels.push(n);
+                }
+            }
+        }
+    }
+    channels
+}
+
+fn comment_value(line: &str, key: &str) -> Option<String> {
+    let rest = line.strip_prefix('*')?.trim_start();
+    if !rest.get(..key.len())?.eq_ignore_ascii_case(key) {
+        return None;
+    }
+    Some(rest[key.len()..].trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TrackInfo;
+
+    const DROP_FRAME_EDL: &str = "TITLE: Test DF
+FCM: DROP FRAME
+
+001  AX       AA     C        00:00:00:00 00:00:10:00 01:00:59;28 01:01:10;00
+* FROM CLIP NAME: ANW1832_001_Morning_Light.wav
+
+002  AX       V      C        00:00:00:00 00:00:10:00 01:00:59;28 01:01:10;00
+* FROM CLIP NAME: Interview.mov
+
+003  AX       A2     C        00:00:00:00 00:00:05:00 01:09:59;28 01:10:05;00
+* FROM CLIP NAME: ANW1832_002_Evening_Shade.wav
+";
+
+    fn parse(text: &str) -> Vec<TrackInfo> {
+        let mut ctx = ParseContext::for_tests();
+        parse_edl_text(&mut ctx, "test.edl", text).unwrap();
+        ctx.tracks_per_file.remove("test.edl").unwrap_or_default()
+    }
+
+    #[test]
+    fn drop_frame_event_across_a_dropped_minute() {
+        let tracks = parse(DROP_FRAME_EDL);
+        let track = tracks.iter().find(|t| t.index == "anw1832_001").unwrap();
+        let record_in = track.first_record_in.unwrap();
+        assert!(record_in.rate.drop_frame);
+        // 01:00:00;00 = 107892 Frames, dazu 59 s 28 Frames ohne ausgelassene Nummern
+        assert_eq!(record_in.frames, 109_690);
+        // 2 Frames bis 01:01:00;02 (;00 und ;01 fallen aus), dann 298 bis 01:01:10;00
+        assert_eq!(track.duration.unwrap().frames, 300);
+    }
+
+    #[test]
+    fn drop_frame_event_into_a_tenth_minute() {
+        let tracks = parse(DROP_FRAME_EDL);
+        let track = tracks.iter().find(|t| t.index == "anw1832_002").unwrap();
+        assert_eq!(track.first_record_in.unwrap().frames, 125_872);
+        // in Minute 10 fallen keine Nummern aus
+        assert_eq!(track.duration.unwrap().frames, 152);
+    }
+
+    #[test]
+    fn video_events_are_skipped() {
+        assert_eq!(parse(DROP_FRAME_EDL).len(), 2);
+    }
+}
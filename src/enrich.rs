@@ -0,0 +1,545 @@
+use crate::cache::StableHasher;
+use crate::labelcodes::LabelCodes;
+use crate::model::{RightsHolder, Role, TrackInfo};
+use anyhow::Result;
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension, Statement};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hasher;
+
+/// Tabelle der Beteiligten je Werk, neben `my_table` (ein Eintrag je Person und Rolle).
+pub const RIGHTS_HOLDERS_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS rights_holders (
+        "index" TEXT NOT NULL,
+        position INTEGER NOT NULL DEFAULT 0,
+        role TEXT NOT NULL,
+        name TEXT NOT NULL,
+        ipi TEXT NOT NULL DEFAULT '',
+        share REAL
+    );
+    CREATE INDEX IF NOT EXISTS idx_rights_index ON rights_holders("index" COLLATE NOCASE);
+"#;
+
+const RIGHTS_HOLDERS_QUERY: &str = r#"
+    SELECT role, name, ipi, share
+    FROM rights_holders
+    WHERE LOWER("index") = LOWER(?1)
+    ORDER BY position
+"#;
+
+/// Vorbereitete Datenbankabfrage für die Anreicherung der Tracks.
+/// Hält die Abfrage über alle Tracks eines Durchlaufs offen.
+pub struct DatabaseLookup<'conn> {
+    stmt: Statement<'conn>,
+    /// Fehlt in älteren Datenbanken, bis sie einmal schreibend geöffnet wurden.
+    rights_stmt: Option<Statement<'conn>>,
+}
+
+impl<'conn> DatabaseLookup<'conn> {
+    pub fn new(conn: &'conn Connection) -> Result<Self> {
+        let stmt = conn.prepare(
+            r#"
+            SELECT kuenstler, titel, labelcode
+            FROM my_table
+            WHERE LOWER("index") = LOWER(?1)
+            LIMIT 1
+        "#,
+        )?;
+        let rights_stmt = conn.prepare(RIGHTS_HOLDERS_QUERY).ok();
+        if rights_stmt.is_none() {
+            info!("Keine Tabelle rights_holders, Beteiligte werden nicht abgeglichen.");
+        }
+        info!("Abfrage vorbereitet.");
+        Ok(Self { stmt, rights_stmt })
+    }
+
+    /// Beteiligte eines Werks aus der Tabelle `rights_holders`, in gespeicherter Reihenfolge.
+    pub fn rights_holders(&mut self, index: &str) -> Vec<RightsHolder> {
+        let Some(stmt) = self.rights_stmt.as_mut() else {
+            return Vec::new();
+        };
+        let rows = stmt.query_map(params![index], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                row.get::<_, Option<f64>>(3)?,
+            ))
+        });
+        match rows {
+            Ok(rows) => rows
+                .filter_map(|row| row.ok())
+                .filter_map(|(role, name, ipi, share)| {
+                    Some(RightsHolder { role: Role::from_key(&role)?, name, ipi, share })
+                })
+                .collect(),
+            Err(e) => {
+                info!("Beteiligte für '{}' nicht lesbar: {}", index, e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Sucht die passende Zeile (passend zum Index) und überschreibt
+    /// Titel/Künstler/Labelcode, falls gefunden. Liefert, ob es einen Treffer gab.
+    pub fn enrich(&mut self, track: &mut TrackInfo, label_codes: &LabelCodes) -> bool {
+        info!("Attempting to match track: '{}'", track.titel);
+        let result = self.stmt.query_row(params![&track.index], |row| {
+            Ok((
+                row.get::<_, String>(1)?, // Titel
+                row.get::<_, String>(0)?, // Künstler
+                row.get::<_, String>(2)?, // Labelcode
+            ))
+        });
+
+        match result {
+            Ok((db_title, db_kuenstler, db_labelcode)) => {
+                // **Daten mit DB-Werten überschreiben**
+                apply_row(track, db_title, db_kuenstler, db_labelcode, label_codes);
+                // Beteiligte aus den Metadaten nur ersetzen, wenn die Datenbank welche kennt
+                let rights_holders = self.rights_holders(&track.index);
+                if !rights_holders.is_empty() {
+                    track.rights_holders = rights_holders;
+                }
+
+                info!(
+                    "DB-Treffer: Titel='{}', Künstler='{}', Labelcode='{}'",
+                    track.titel, track.kuenstler, track.label_code
+                );
+                true
+            }
+            Err(_) => {
+                info!(
+                    "Kein DB-Treffer für '{}', behalte ursprüngliche Werte.",
+                    track.index
+                );
+                false
+            }
+        }
+    }
+}
+
+/// Ersetzt die Beteiligten eines Werks; läuft in der Transaktion des Aufrufers.
+fn replace_rights_holders(conn: &Connection, index: &str, holders: &[RightsHolder]) -> Result<()> {
+    conn.execute(r#"DELETE FROM rights_holders WHERE LOWER("index") = LOWER(?1)"#, params![index])?;
+    for (position, holder) in holders.iter().enumerate() {
+        conn.execute(
+            r#"INSERT INTO rights_holders ("index", position, role, name, ipi, share) VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+            params![index, position as i64, holder.role.key(), holder.name, holder.ipi, holder.share],
+        )?;
+    }
+    info!("{} Beteiligte für '{}' gespeichert.", holders.len(), index);
+    Ok(())
+}
+
+/// Ein Eintrag in `my_table` samt Beteiligten, so wie er geschrieben bzw. wiederhergestellt wird.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseRow {
+    pub index: String,
+    pub titel: String,
+    pub kuenstler: String,
+    pub label_code: String,
+    pub rights_holders: Vec<RightsHolder>,
+}
+
+/// Liest einen Eintrag samt Beteiligten; `None`, wenn es den Index nicht gibt.
+pub fn load_database_row(conn: &Connection, index: &str) -> Result<Option<DatabaseRow>> {
+    let row = conn
+        .query_row(
+            r#"SELECT "index", titel, kuenstler, labelcode FROM my_table WHERE LOWER("index") = LOWER(?1) LIMIT 1"#,
+            params![index],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                    row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                    row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                ))
+            },
+        )
+        .optional()?;
+    let Some((index, titel, kuenstler, label_code)) = row else {
+        return Ok(None);
+    };
+    let rights_holders = DatabaseLookup::new(conn)?.rights_holders(&index);
+    Ok(Some(DatabaseRow { index, titel, kuenstler, label_code, rights_holders }))
+}
+
+/// Überschreibt Titel, Künstler, Labelcode und Beteiligte eines Eintrags oder legt ihn an.
+/// Gibt zurück, ob der Eintrag schon vorhanden war.
+pub fn write_database_row(conn: &Connection, row: &DatabaseRow) -> Result<bool> {
+    // Beteiligte gehören zum Eintrag und werden in derselben Transaktion gespeichert
+    let tx = conn.unchecked_transaction()?;
+    let updated = tx.execute(
+        r#"UPDATE my_table SET titel = ?1, kuenstler = ?2, labelcode = ?3 WHERE LOWER("index") = LOWER(?4)"#,
+        params![row.titel, row.kuenstler, row.label_code, row.index],
+    )?;
+    if updated == 0 {
+        tx.execute(
+            r#"INSERT INTO my_table ("index", titel, kuenstler, labelcode) VALUES (?1, ?2, ?3, ?4)"#,
+            params![row.index, row.titel, row.kuenstler, row.label_code],
+        )?;
+    }
+    replace_rights_holders(&tx, &row.index, &row.rights_holders)?;
+    tx.commit()?;
+    Ok(updated > 0)
+}
+
+/// Entfernt einen Eintrag samt Beteiligten.
+pub fn delete_database_row(conn: &Connection, index: &str) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(r#"DELETE FROM my_table WHERE LOWER("index") = LOWER(?1)"#, params![index])?;
+    tx.execute(r#"DELETE FROM rights_holders WHERE LOWER("index") = LOWER(?1)"#, params![index])?;
+    tx.commit()?;
+    info!("Eintrag '{}' gelöscht.", index);
+    Ok(())
+}
+
+/// Hash über alle Einträge und Beteiligten, Teil des Cache-Schlüssels der eingelesenen Dateien.
+/// Sortiert nach Inhalt, damit gelöschte und wieder angelegte Einträge gleich hashen.
+pub fn database_hash(conn: &Connection) -> Result<u64> {
+    let mut hasher = StableHasher::default();
+    let mut stmt = conn.prepare(
+        r#"SELECT "index", titel, kuenstler, labelcode FROM my_table ORDER BY "index", titel, kuenstler, labelcode"#,
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        for column in 0..4 {
+            hasher.write_str(&row.get::<_, Option<String>>(column)?.unwrap_or_default());
+        }
+    }
+    let mut stmt = conn.prepare(
+        r#"SELECT "index", position, role, name, ipi, share FROM rights_holders
+           ORDER BY "index", position, role, name, ipi, share"#,
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        hasher.write_str(&row.get::<_, String>(0)?);
+        hasher.write(&row.get::<_, i64>(1)?.to_le_bytes());
+        for column in 2..5 {
+            hasher.write_str(&row.get::<_, String>(column)?);
+        }
+        hasher.write(&row.get::<_, Option<f64>>(5)?.map_or(u64::MAX, f64::to_bits).to_le_bytes());
+    }
+    Ok(hasher.finish())
+}
+
+/// Übernimmt Titel/Künstler/Labelcode aus der Datenbank in den Track.
+fn apply_row(track: &mut TrackInfo, titel: String, kuenstler: String, label_code: String, label_codes: &LabelCodes) {
+    track.titel = titel;
+    track.kuenstler = kuenstler;
+    // Leerer Labelcode in der DB => über die Präfix-Tabelle ermitteln
+    track.label_code = if label_code.trim().is_empty() {
+        label_codes
+            .lookup(&track.index)
+            .map(|m| m.code.to_string())
+            .unwrap_or_default()
+    } else {
+        label_code
+    };
+}
+
+/// Vorschläge mit geringerer Konfidenz werden nicht angezeigt.
+const MIN_CONFIDENCE: f64 = 0.5;
+const MAX_CANDIDATES: usize = 5;
+/// So viele Einträge mit den meisten gemeinsamen Trigrammen werden vollständig bewertet.
+const PREFILTER_LIMIT: usize = 500;
+
+/// Möglicher Datenbankeintrag für einen Track ohne exakten Treffer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    pub index: String,
+    pub titel: String,
+    pub kuenstler: String,
+    pub label_code: String,
+    /// 0.0 bis 1.0
+    pub confidence: f64,
+}
+
+impl Candidate {
+    pub fn apply(&self, track: &mut TrackInfo, label_codes: &LabelCodes) {
+        apply_row(track, self.titel.clone(), self.kuenstler.clone(), self.label_code.clone(), label_codes);
+    }
+}
+
+/// Vorschläge für einen Track (Index wie eingelesen), bester zuerst.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchCandidates {
+    pub index: String,
+    pub titel: String,
+    pub kuenstler: String,
+    pub candidates: Vec<Candidate>,
+}
+
+struct IndexedRow {
+    index: String,
+    titel: String,
+    kuenstler: String,
+    label_code: String,
+    index_key: String,
+    index_grams: Vec<u64>,
+    text_grams: Vec<u64>,
+}
+
+/// Unscharfe Suche über alle Datenbankeinträge, für Tracks ohne exakten Index-Treffer.
+/// Die Tabelle wird einmal eingelesen und über ihre Trigramme indiziert.
+pub struct CandidateFinder {
+    rows: Vec<IndexedRow>,
+    /// Trigramm -> Einträge (Position in `rows`), deren Index oder Titel/Künstler es enthält
+    postings: HashMap<u64, Vec<usize>>,
+}
+
+impl CandidateFinder {
+    pub fn load(conn: &Connection) -> Result<Self> {
+        let mut stmt = conn.prepare(r#"SELECT "index", titel, kuenstler, labelcode FROM my_table"#)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+                    row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                    row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                    row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                ))
+            })?
+            .filter_map(|row| row.ok())
+            .map(|(index, titel, kuenstler, label_code)| {
+                let index_key = normalize_index(&index);
+                IndexedRow {
+                    index_grams: trigrams(&index_key),
+                    text_grams: trigrams(&format!("{} {}", titel, kuenstler)),
+                    index_key,
+                    index,
+                    titel,
+                    kuenstler,
+                    label_code,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut postings: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (position, row) in rows.iter().enumerate() {
+            for gram in merge_grams(&row.index_grams, &row.text_grams) {
+                postings.entry(gram).or_default().push(position);
+            }
+        }
+        info!("{} Datenbankeinträge für die unscharfe Suche vorbereitet.", rows.len());
+        Ok(Self { rows, postings })
+    }
+
+    /// Vorfilter: Einträge mit mindestens einem gemeinsamen Trigramm, die mit den
+    /// meisten gemeinsamen zuerst, höchstens `PREFILTER_LIMIT`.
+    fn prefilter(&self, grams: &[u64]) -> Vec<&IndexedRow> {
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+        for gram in grams {
+            for &position in self.postings.get(gram).into_iter().flatten() {
+                *shared.entry(position).or_default() += 1;
+            }
+        }
+        let mut ranked: Vec<(usize, usize)> = shared.into_iter().collect();
+        ranked.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked.truncate(PREFILTER_LIMIT);
+        ranked.into_iter().map(|(position, _)| &self.rows[position]).collect()
+    }
+
+    /// Rangliste möglicher Einträge: Ähnlichkeit des normalisierten Index (Trigramme
+    /// bzw. Editierabstand) und,
+    /// falls der Track schon Titel/Künstler hat, Trigramm-Ähnlichkeit von Titel und Künstler.
+    /// Bewertet werden nur die Einträge aus dem Trigramm-Vorfilter.
+    pub fn candidates(&self, track: &TrackInfo) -> Vec<Candidate> {
+        let index_key = normalize_index(&track.index);
+        let index_grams = trigrams(&index_key);
+        let text = format!("{} {}", track.titel, track.kuenstler);
+        let text_grams = trigrams(&text);
+
+        let mut candidates: Vec<Candidate> = self
+            .prefilter(&merge_grams(&index_grams, &text_grams))
+            .into_iter()
+            .filter_map(|row| {
+                let index_score = if !index_key.is_empty() && index_key == row.index_key {
+                    1.0
+                } else {
+                    // Tippfehler in kurzen Indizes treffen die Trigramme stark, daher auch Editierabstand
+                    similarity(&index_grams, &row.index_grams).max(edit_similarity(&index_key, &row.index_key))
+                };
+                let confidence = if text_grams.is_empty() || row.text_grams.is_empty() {
+                    index_score
+                } else {
+                    0.6 * index_score + 0.4 * similarity(&text_grams, &row.text_grams)
+                };
+                (confidence >= MIN_CONFIDENCE).then(|| Candidate {
+                    index: row.index.clone(),
+                    titel: row.titel.clone(),
+                    kuenstler: row.kuenstler.clone(),
+                    label_code: row.label_code.clone(),
+                    confidence,
+                })
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap_or(Ordering::Equal));
+        candidates.truncate(MAX_CANDIDATES);
+        candidates
+    }
+}
+
+/// Index ohne Trennzeichen, in Kleinbuchstaben und ohne führende Nullen in jeder Zahl
+/// ("ABC_0012" und "abc-12" ergeben beide "abc12", "ANW1832_001" wie "ANW1832_1").
+fn normalize_index(index: &str) -> String {
+    let mut key = String::with_capacity(index.len());
+    let mut in_number = false;
+    let mut chars = index.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !c.is_alphanumeric() {
+            // jedes Trennzeichen beginnt einen neuen Zahlenblock
+            in_number = false;
+            continue;
+        }
+        // eine Null bleibt, wenn der Block nur aus Nullen besteht
+        if c == '0' && !in_number && chars.peek().is_some_and(|next| next.is_ascii_digit()) {
+            continue;
+        }
+        in_number = c.is_ascii_digit();
+        key.extend(c.to_lowercase());
+    }
+    key
+}
+
+/// Sortierte, eindeutige Trigramme des Textes (je drei Zeichen in einer Zahl).
+fn trigrams(text: &str) -> Vec<u64> {
+    let words = text
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if words.is_empty() {
+        return Vec::new();
+    }
+    let chars: Vec<char> = format!("  {} ", words).chars().collect();
+    let mut grams: Vec<u64> = chars
+        .windows(3)
+        .map(|w| (w[0] as u64) << 42 | (w[1] as u64) << 21 | w[2] as u64)
+        .collect();
+    grams.sort_unstable();
+    grams.dedup();
+    grams
+}
+
+/// Vereinigung zweier sortierter Trigramm-Mengen.
+fn merge_grams(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let mut grams = [a, b].concat();
+    grams.sort_unstable();
+    grams.dedup();
+    grams
+}
+
+/// Jaccard-Ähnlichkeit zweier sortierter Trigramm-Mengen.
+fn similarity(a: &[u64], b: &[u64]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let (mut i, mut j, mut shared) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                shared += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+/// 1 - Levenshtein-Abstand / Länge des längeren Textes.
+fn edit_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 || a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finder(rows: &[(&str, &str, &str)]) -> CandidateFinder {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(r#"CREATE TABLE my_table ("index" TEXT, titel TEXT, kuenstler TEXT, labelcode TEXT);"#)
+            .unwrap();
+        for (index, titel, kuenstler) in rows {
+            conn.execute(
+                r#"INSERT INTO my_table ("index", titel, kuenstler, labelcode) VALUES (?1, ?2, ?3, '')"#,
+                params![index, titel, kuenstler],
+            )
+            .unwrap();
+        }
+        CandidateFinder::load(&conn).unwrap()
+    }
+
+    fn track(index: &str, titel: &str, kuenstler: &str) -> TrackInfo {
+        TrackInfo {
+            key: index.to_string(),
+            index: index.to_string(),
+            titel: titel.to_string(),
+            kuenstler: kuenstler.to_string(),
+            duration: None,
+            label_code: String::new(),
+            isrc: String::new(),
+            usages: Vec::new(),
+            versions: Vec::new(),
+            first_record_in: None,
+            last_record_out: None,
+            rights_holders: Vec::new(),
+            parsed: None,
+        }
+    }
+
+    #[test]
+    fn leading_zeros_are_dropped_in_every_number_block() {
+        assert_eq!(normalize_index("ANW1832_001"), "anw18321");
+        assert_eq!(normalize_index("ANW1832_1"), "anw18321");
+        assert_eq!(normalize_index("ANW1832-001"), normalize_index("anw1832_1"));
+        assert_eq!(normalize_index("ABC_0012"), "abc12");
+        assert_eq!(normalize_index("ABC_000"), "abc0");
+    }
+
+    #[test]
+    fn missing_leading_zero_ranks_the_entry_first() {
+        let finder = finder(&[
+            ("ANW1832_001", "Morning Light", "Composer A"),
+            ("ANW1832_011", "Evening Shade", "Composer B"),
+            ("XYZ0001_001", "Something Else", "Composer C"),
+        ]);
+        let candidates = finder.candidates(&track("ANW1832_1", "", ""));
+        assert_eq!(candidates[0].index, "ANW1832_001");
+        assert_eq!(candidates[0].confidence, 1.0);
+        assert!(candidates.iter().all(|c| c.index != "XYZ0001_001"));
+    }
+
+    #[test]
+    fn title_breaks_ties_between_similar_indices() {
+        let finder = finder(&[
+            ("BMGPM123_01", "Morning Light", "Composer A"),
+            ("BMGPM123_02", "Evening Shade", "Composer B"),
+        ]);
+        let candidates = finder.candidates(&track("BMGPM123_0", "evening shade", "composer b"));
+        assert_eq!(candidates[0].index, "BMGPM123_02");
+    }
+}
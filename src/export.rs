@@ -1,9 +1,13 @@
 // src/export.rs
 use crate::app::GemaLauncherApp;
-use anyhow::Result;
-use log::{error, info};
-use std::fs::File;
-use std::io::Write;
+use crate::diagnostic::{Diagnostic, DiagnosticKind, Severity};
+use crate::model::TrackInfo;
+use crate::production::ProductionInfo;
+use crate::report::{policy_diagnostics, report_header, report_rows, report_total};
+use crate::settings::{CsvEncoding, CsvQuoting, ExportProfile};
+use anyhow::{bail, Result};
+use log::info;
+use std::fs;
 use std::path::{Path, PathBuf};
 use rfd::FileDialog;
 
@@ -31,63 +35,153 @@ impl GemaLauncherApp {
             }
         };
 
-        for (filename, tracks) in &self.tracks_per_file {
-            if tracks.is_empty() {
+        let profile = self.settings.export_profile();
+        info!("Exportiere mit Profil '{}'", profile.name);
+
+        // Meldungen erst nach der Schleife aufnehmen (tracks_per_file ist ausgeliehen)
+        // Reihenfolge wie in der Dateiliste
+        let mut diagnostics = Vec::new();
+        for filename in &self.filenames {
+            let Some(tracks) = self.tracks_per_file.get(filename).filter(|t| !t.is_empty()) else {
                 continue;
-            }
+            };
 
             let path = Path::new(filename);
             let base_name = path.file_stem().unwrap_or_default().to_str().unwrap_or("output");
             let formatted_name = format!("{}_formatted.csv", base_name);
             let output_path = export_dir.join(&formatted_name);
 
-            match File::create(&output_path) {
-                Ok(mut f) => {
-                    // CSV Header
-                    if let Err(e) = writeln!(f, "Index,Titel,Künstler,Dauer,Labelcode") {
-                        let error_msg = format!("CSV-Fehler: {}", e);
-                        self.error_messages.push(error_msg.clone());
-                        error!("{}", error_msg);
-                        continue;
-                    }
-
-                    // CSV Daten
-                    for track in tracks {
-                        let duration = track.duration.map_or(String::new(), |d| self.format_duration(d));
-                        // Verhindert, dass Excel die Zeit verschluckt. Z.B. "' 12:34" 
-                        let formatted_duration = format!("' {}", duration);
-                        if let Err(e) = writeln!(
-                            f,
-                            "{},{},{},{},{}",
-                            track.index,
-                            track.titel,
-                            track.kuenstler,
-                            formatted_duration,
-                            track.label_code
-                        ) {
-                            let error_msg = format!("CSV-Fehler: {}", e);
-                            self.error_messages.push(error_msg.clone());
-                            error!("{}", error_msg);
-                            continue;
-                        }
-                    }
+            let csv_text = match self.build_csv(tracks, self.production_for(filename), &profile) {
+                Ok(text) => text,
+                Err(e) => {
+                    diagnostics.push(
+                        Diagnostic::error(DiagnosticKind::Export, format!("CSV-Fehler: {}", e))
+                            .file(filename)
+                            .suggest("Trennzeichen im Exportprofil prüfen"),
+                    );
+                    continue;
+                }
+            };
+
+            diagnostics.extend(policy_diagnostics(tracks, &self.settings.duration_policy(), filename));
 
+            let (bytes, lossy) = encode_csv(&csv_text, profile.encoding);
+            if lossy {
+                let msg = format!(
+                    "Nicht alle Zeichen in {} sind in {} darstellbar und wurden ersetzt.",
+                    formatted_name,
+                    profile.encoding.label()
+                );
+                diagnostics.push(
+                    Diagnostic::warning(DiagnosticKind::Export, msg)
+                        .file(filename)
+                        .suggest("Exportprofil mit UTF-8-Kodierung verwenden"),
+                );
+            }
+
+            match fs::write(&output_path, bytes) {
+                Ok(()) => {
                     info!("CSV erfolgreich exportiert nach {}", output_path.display());
                 }
                 Err(e) => {
-                    let error_msg = format!("Datei-Fehler: {}", e);
-                    self.error_messages.push(error_msg.clone());
-                    error!("{}", error_msg);
+                    diagnostics.push(
+                        Diagnostic::error(
+                            DiagnosticKind::Export,
+                            format!("Datei-Fehler beim Schreiben von {}: {}", output_path.display(), e),
+                        )
+                        .file(filename),
+                    );
                 }
             }
         }
 
-        rfd::MessageDialog::new()
-            .set_title("Erfolg")
-            .set_description("Alle CSVs wurden erfolgreich exportiert.")
-            .set_buttons(rfd::MessageButtons::Ok)
-            .show();
+        let errors = diagnostics.iter().filter(|d| d.severity == Severity::Error).count();
+        for diagnostic in diagnostics {
+            self.report(diagnostic);
+        }
+
+        if errors == 0 {
+            rfd::MessageDialog::new()
+                .set_title("Erfolg")
+                .set_description("Alle CSVs wurden erfolgreich exportiert.")
+                .set_buttons(rfd::MessageButtons::Ok)
+                .show();
+        } else {
+            // Fehler sichtbar machen, auch wenn der Filter sie gerade ausblendet
+            self.hidden_severities.retain(|s| *s != Severity::Error);
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Warning)
+                .set_title("Export unvollständig")
+                .set_description(format!(
+                    "Beim Export sind {} Fehler aufgetreten. Details stehen unter \"Meldungen\".",
+                    errors
+                ))
+                .set_buttons(rfd::MessageButtons::Ok)
+                .show();
+        }
 
         Ok(())
     }
+
+    /// Schreibt die Tracks einer Datei als CSV (RFC 4180) mit den Optionen des Profils.
+    /// Gehört die Datei zu einer Produktion, steht deren Kopfblock vor der Tabelle.
+    fn build_csv(&self, tracks: &[TrackInfo], production: Option<&ProductionInfo>, profile: &ExportProfile) -> Result<String> {
+        if !profile.delimiter.is_ascii() || profile.delimiter == '"' {
+            bail!("Ungültiges Trennzeichen '{}'", profile.delimiter);
+        }
+
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(profile.delimiter as u8)
+            .quote_style(match profile.quoting {
+                CsvQuoting::Necessary => csv::QuoteStyle::Necessary,
+                CsvQuoting::Always => csv::QuoteStyle::Always,
+            })
+            .terminator(csv::Terminator::CRLF)
+            .from_writer(Vec::new());
+
+        let mode = self.settings.report_mode;
+
+        let header = report_header(mode);
+
+        // Kopfblock (Bezeichnung, Wert), auf die Spaltenzahl aufgefüllt, danach eine Leerzeile
+        if let Some(production) = production.filter(|p| !p.is_empty()) {
+            for (label, value) in production.fields() {
+                let mut record = vec![label.to_string(), value.clone()];
+                record.resize(header.len(), String::new());
+                writer.write_record(&record)?;
+            }
+            writer.write_record(vec![""; header.len()])?;
+        }
+
+        // CSV Header
+        writer.write_record(header)?;
+
+        // CSV Daten (je Track oder je Verwendung mit Cue-Nummer)
+        let policy = self.settings.duration_policy();
+        let rows = report_rows(tracks, mode, &policy);
+        for row in &rows {
+            writer.write_record(self.report_cells(row, mode, profile.excel_text_duration))?;
+        }
+        if policy.total_line {
+            writer.write_record(self.report_total_cells(report_total(&rows), mode, profile.excel_text_duration))?;
+        }
+
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+}
+
+/// Kodiert den fertigen CSV-Text. Liefert zusätzlich, ob Zeichen ersetzt werden mussten.
+fn encode_csv(text: &str, encoding: CsvEncoding) -> (Vec<u8>, bool) {
+    match encoding {
+        CsvEncoding::Utf8Bom => {
+            let mut bytes = vec![0xEF, 0xBB, 0xBF];
+            bytes.extend_from_slice(text.as_bytes());
+            (bytes, false)
+        }
+        CsvEncoding::Utf8 => (text.as_bytes().to_vec(), false),
+        CsvEncoding::Windows1252 => {
+            let (bytes, _, lossy) = encoding_rs::WINDOWS_1252.encode(text);
+            (bytes.into_owned(), lossy)
+        }
+    }
 }
@@ -0,0 +1,240 @@
+use crate::cache::StableHasher;
+use crate::diagnostic::{Diagnostic, DiagnosticKind};
+use anyhow::{Context, Result};
+use log::{error, info};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::BufReader;
+use std::path::Path;
+
+pub const FILENAME_RULES_PATH: &str = "src/assets/filename_rules.json";
+pub const VERSION_SUFFIXES_PATH: &str = "src/assets/version_suffixes.json";
+
+/// Eine Regel zum Zerlegen von Dateinamen einer Library.
+/// Das Muster benennt seine Gruppen `index`, `title`, `artist` und optional `version`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilenameRule {
+    pub name: String,
+    /// Labelcode-Präfix (wie in labelcodes.json), mit dem der Dateiname beginnen muss; leer = alle.
+    #[serde(default)]
+    pub prefix: String,
+    /// Höhere Priorität wird zuerst geprüft; bei Gleichstand zählt die Reihenfolge in der Datei.
+    #[serde(default)]
+    pub priority: i32,
+    pub pattern: String,
+}
+
+/// Ergebnis einer passenden Regel.
+#[derive(Debug, Clone, Default)]
+pub struct RuleMatch {
+    pub rule: String,
+    pub index: String,
+    pub title: String,
+    pub artist: String,
+    pub version: String,
+}
+
+#[derive(Default)]
+pub struct FilenameRules {
+    rules: Vec<(FilenameRule, Regex)>,
+    /// Versionsendungen (Stems, Kurzfassungen, ...), die vor dem Zerlegen abgetrennt werden.
+    pub versions: VersionSuffixes,
+    /// Regeln mit ungültigem Muster; sie werden übersprungen und bei jedem Einlesen gemeldet.
+    pub errors: Vec<Diagnostic>,
+}
+
+impl FilenameRules {
+    pub fn load(path: &str) -> Result<Self> {
+        if !Path::new(path).exists() {
+            info!("Regeldatei '{}' nicht gefunden, verwende Standardzerlegung.", path);
+            return Ok(Self::default());
+        }
+
+        let file = File::open(path)
+            .with_context(|| format!("Kann Regeldatei '{}' nicht öffnen.", path))?;
+        let rules: Vec<FilenameRule> = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Regeldatei '{}' ist kein gültiges JSON.", path))?;
+        info!("{} Dateinamen-Regeln geladen.", rules.len());
+        Ok(Self::new(rules))
+    }
+
+    pub fn new(rules: Vec<FilenameRule>) -> Self {
+        let mut errors = Vec::new();
+        let mut compiled: Vec<(FilenameRule, Regex)> = rules
+            .into_iter()
+            .filter_map(|rule| match Regex::new(&format!("(?i){}", rule.pattern)) {
+                Ok(re) => Some((rule, re)),
+                Err(e) => {
+                    let msg = format!("Dateinamen-Regel '{}' hat ein ungültiges Muster: {}", rule.name, e);
+                    error!("{}", msg);
+                    errors.push(
+                        Diagnostic::error(DiagnosticKind::Config, msg)
+                            .file(FILENAME_RULES_PATH)
+                            .suggest("Muster in der Regeldatei korrigieren und \"Neu laden\" wählen"),
+                    );
+                    None
+                }
+            })
+            .collect();
+        // stabil sortieren, damit die Dateireihenfolge bei gleicher Priorität erhalten bleibt
+        compiled.sort_by_key(|(rule, _)| Reverse(rule.priority));
+        Self { rules: compiled, versions: VersionSuffixes::default(), errors }
+    }
+
+    pub fn with_versions(mut self, versions: VersionSuffixes) -> Self {
+        self.versions = versions;
+        self
+    }
+
+    /// Hash der gültigen Regeln (in Prüfreihenfolge) und Versionsendungen,
+    /// Teil des Cache-Schlüssels der eingelesenen Dateien.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+        for rule in self.rules() {
+            hasher.write_str(&rule.name);
+            hasher.write_str(&rule.prefix);
+            hasher.write(&rule.priority.to_le_bytes());
+            hasher.write_str(&rule.pattern);
+        }
+        for suffix in self.versions.suffixes() {
+            hasher.write_str(suffix);
+        }
+        hasher.finish()
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &FilenameRule> {
+        self.rules.iter().map(|(rule, _)| rule)
+    }
+
+    /// Erste passende Regel für einen Dateinamen ohne Endung.
+    pub fn apply(&self, base_name: &str) -> Option<RuleMatch> {
+        let lower = base_name.to_lowercase();
+        self.rules
+            .iter()
+            .filter(|(rule, _)| lower.starts_with(&rule.prefix.to_lowercase()))
+            .find_map(|(rule, re)| {
+                let caps = re.captures(base_name)?;
+                let group = |name: &str| caps.name(name).map_or("", |m| m.as_str()).to_string();
+                Some(RuleMatch {
+                    rule: rule.name.clone(),
+                    index: group("index").trim_end_matches('_').to_string(),
+                    title: group("title"),
+                    artist: group("artist"),
+                    version: group("version"),
+                })
+            })
+    }
+}
+
+/// Wortschatz für Versionsendungen wie `_30sec`, ` - Alt` oder `(Stem_Bass)`.
+/// `#` steht für eine Zahl, `*` für ein beliebiges Wort. Eine Endung zählt nur hinter
+/// einem Trenner (`_`, ` - `) oder in Klammern, damit Titelwörter erhalten bleiben.
+/// Frisst eine Endung wie `#` die Indexnummer, fängt `parse_track_filename` das ab.
+#[derive(Default)]
+pub struct VersionSuffixes {
+    suffixes: Vec<(String, Regex)>,
+}
+
+impl VersionSuffixes {
+    pub fn load(path: &str) -> Result<Self> {
+        if !Path::new(path).exists() {
+            info!("Versionsliste '{}' nicht gefunden, Versionen werden nicht erkannt.", path);
+            return Ok(Self::default());
+        }
+
+        let file = File::open(path)
+            .with_context(|| format!("Kann Versionsliste '{}' nicht öffnen.", path))?;
+        let suffixes: Vec<String> = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Versionsliste '{}' ist kein gültiges JSON.", path))?;
+        info!("{} Versionsendungen geladen.", suffixes.len());
+        Ok(Self::new(suffixes))
+    }
+
+    pub fn new(suffixes: Vec<String>) -> Self {
+        let suffixes = suffixes
+            .into_iter()
+            .filter(|suffix| !suffix.trim().is_empty())
+            .map(|suffix| {
+                let pattern: String = suffix
+                    .trim()
+                    .chars()
+                    .map(|c| match c {
+                        '#' => r"\d+".to_string(),
+                        '*' => r"[^_\s()\[\]]+".to_string(),
+                        c => regex::escape(&c.to_string()),
+                    })
+                    .collect();
+                let re = Regex::new(&format!(r"(?i)(?:(?:_|\s+-\s+)(?:{0})|\s*\((?:{0})\)|\s*\[(?:{0})\])$", pattern))
+                    .expect("escaped suffix pattern");
+                (suffix, re)
+            })
+            .collect();
+        Self { suffixes }
+    }
+
+    pub fn suffixes(&self) -> impl Iterator<Item = &str> {
+        self.suffixes.iter().map(|(suffix, _)| suffix.as_str())
+    }
+
+    /// Trennt Versionsendungen vom Ende ab (auch mehrere, z.B. `_Alt_30sec`).
+    /// Liefert den Rest und die Version ohne Trenner und Klammern, Teile mit `_` verbunden.
+    pub fn strip<'a>(&self, base_name: &'a str) -> (&'a str, String) {
+        let mut rest = base_name;
+        let mut parts = Vec::new();
+        loop {
+            // die am weitesten vorne beginnende Endung gewinnt, damit `_Stem_Bass` nicht nur `_Bass` verliert
+            let earliest = self
+                .suffixes
+                .iter()
+                .filter_map(|(_, re)| re.find(rest))
+                .filter(|m| m.start() > 0)
+                .min_by_key(|m| m.start());
+            let Some(m) = earliest else { break };
+            let part = m.as_str().trim_start_matches(|c: char| c == '_' || c == '-' || c.is_whitespace());
+            parts.push(part.trim_matches(|c| matches!(c, '(' | ')' | '[' | ']')).to_string());
+            rest = &rest[..m.start()];
+        }
+        parts.reverse();
+        (rest, parts.join("_"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suffixes() -> VersionSuffixes {
+        VersionSuffixes::new(["#sec", "#", "Alt", "Stem_*"].map(String::from).to_vec())
+    }
+
+    #[test]
+    fn several_suffixes_are_stripped_from_the_end() {
+        assert_eq!(suffixes().strip("Song_Alt_30sec"), ("Song", "Alt_30sec".to_string()));
+    }
+
+    #[test]
+    fn suffixes_in_brackets_and_after_dashes_are_stripped() {
+        assert_eq!(suffixes().strip("Song - Alt (30sec)"), ("Song", "Alt_30sec".to_string()));
+        assert_eq!(suffixes().strip("Song [60]"), ("Song", "60".to_string()));
+    }
+
+    #[test]
+    fn number_only_suffix_needs_a_separator() {
+        assert_eq!(suffixes().strip("Track_60"), ("Track", "60".to_string()));
+        assert_eq!(suffixes().strip("Track60"), ("Track60", String::new()));
+    }
+
+    #[test]
+    fn wildcard_suffix_keeps_the_whole_stem_name() {
+        assert_eq!(suffixes().strip("Song_Stem_Bass"), ("Song", "Stem_Bass".to_string()));
+    }
+
+    #[test]
+    fn title_words_are_not_stripped() {
+        assert_eq!(suffixes().strip("Full Moon"), ("Full Moon", String::new()));
+        assert_eq!(suffixes().strip("Salt"), ("Salt", String::new()));
+    }
+}
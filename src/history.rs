@@ -0,0 +1,92 @@
+use crate::enrich::DatabaseRow;
+use crate::model::TrackOverride;
+use crate::production::ProductionGroup;
+use crate::timecode::FrameRate;
+use std::collections::{HashMap, HashSet};
+
+/// Ältere Einträge fallen aus dem Verlauf.
+const MAX_HISTORY: usize = 100;
+
+/// Handänderung eines Tracks vorher und nachher (`None` = keine Handänderung).
+#[derive(Debug, Clone)]
+pub struct TrackChange {
+    pub file: String,
+    /// Zeile, siehe `TrackInfo::key`
+    pub key: String,
+    pub before: Option<TrackOverride>,
+    pub after: Option<TrackOverride>,
+}
+
+/// Dateiliste mit allem, was beim Entfernen einer Datei verloren geht.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileList {
+    pub filenames: Vec<String>,
+    pub frame_rate_overrides: HashMap<String, FrameRate>,
+    pub track_overrides: HashMap<String, HashMap<String, TrackOverride>>,
+    pub dismissed_candidates: HashSet<String>,
+    pub productions: Vec<ProductionGroup>,
+}
+
+/// Rückgängig machbare Änderung mit dem Stand davor und danach.
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// Handänderungen an einem oder mehreren Tracks
+    Tracks(Vec<TrackChange>),
+    Files { before: FileList, after: FileList },
+    /// Geschriebener Datenbankeintrag; `before` = `None`, wenn er neu angelegt wurde.
+    Database { before: Option<DatabaseRow>, after: DatabaseRow },
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub label: String,
+    pub command: Command,
+}
+
+/// Verlauf für Rückgängig/Wiederholen: ausgeführte Änderungen, neueste zuletzt,
+/// und rückgängig gemachte, die zuletzt rückgängig gemachte zuletzt.
+#[derive(Default)]
+pub struct History {
+    done: Vec<HistoryEntry>,
+    undone: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Nimmt eine neue Änderung auf; rückgängig gemachte lassen sich danach nicht mehr wiederholen.
+    pub fn record(&mut self, label: impl Into<String>, command: Command) {
+        self.push_done(HistoryEntry { label: label.into(), command });
+        self.undone.clear();
+    }
+
+    pub fn pop_undo(&mut self) -> Option<HistoryEntry> {
+        self.done.pop()
+    }
+
+    pub fn pop_redo(&mut self) -> Option<HistoryEntry> {
+        self.undone.pop()
+    }
+
+    pub fn push_done(&mut self, entry: HistoryEntry) {
+        self.done.push(entry);
+        if self.done.len() > MAX_HISTORY {
+            self.done.remove(0);
+        }
+    }
+
+    pub fn push_undone(&mut self, entry: HistoryEntry) {
+        self.undone.push(entry);
+    }
+
+    pub fn done(&self) -> &[HistoryEntry] {
+        &self.done
+    }
+
+    pub fn undone(&self) -> &[HistoryEntry] {
+        &self.undone
+    }
+
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+}
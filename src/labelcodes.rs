@@ -1,7 +1,9 @@
+use crate::cache::StableHasher;
 use anyhow::{Context, Result};
 use log::{info, error};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
+use std::hash::Hasher;
 use std::io::BufReader;
 use std::path::Path;
 
@@ -28,3 +30,70 @@ pub fn load_labelcodes(path: &str) -> Result<HashMap<String, String>> {
 
     Ok(label_dict)
 }
+
+/// Ergebnis einer Labelcode-Suche: der längste passende Präfix gewinnt.
+#[derive(Debug)]
+pub struct LabelMatch<'a> {
+    pub prefix: &'a str,
+    pub code: &'a str,
+    /// Kürzere Präfixe, die ebenfalls passen, aber einen anderen Code liefern.
+    pub conflicts: Vec<(&'a str, &'a str)>,
+}
+
+/// Präfix-Tabelle für Labelcodes mit deterministischer Longest-Match-Suche.
+/// Die Einträge sind nach Länge (absteigend) und dann alphabetisch sortiert,
+/// damit das Ergebnis nicht mehr von der HashMap-Reihenfolge abhängt.
+#[derive(Debug, Default)]
+pub struct LabelCodes {
+    entries: Vec<(String, String)>,
+}
+
+impl LabelCodes {
+    pub fn new(label_dict: HashMap<String, String>) -> Self {
+        let mut entries: Vec<(String, String)> = label_dict
+            .into_iter()
+            .map(|(prefix, code)| (prefix.trim().to_uppercase(), code.trim().to_string()))
+            .filter(|(prefix, _)| !prefix.is_empty())
+            .collect();
+        entries.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.cmp(b)));
+        Self { entries }
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(Self::new(load_labelcodes(path)?))
+    }
+
+    /// Hash aller Einträge, Teil des Cache-Schlüssels der eingelesenen Dateien.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+        for (prefix, code) in &self.entries {
+            hasher.write_str(prefix);
+            hasher.write_str(code);
+        }
+        hasher.finish()
+    }
+
+    /// Sucht den längsten Präfix, mit dem der Index beginnt.
+    pub fn lookup(&self, index_str: &str) -> Option<LabelMatch<'_>> {
+        let index_upper = index_str.to_uppercase();
+        let mut matches = self
+            .entries
+            .iter()
+            .filter(|(prefix, _)| index_upper.starts_with(prefix.as_str()));
+
+        let (prefix, code) = matches.next()?;
+        // Gleiche Paare (z.B. "mh" und "MH" in der Datei) nur einmal melden, auch wenn sie nicht aufeinander folgen
+        let mut seen = HashSet::new();
+        let conflicts: Vec<(&str, &str)> = matches
+            .filter(|(_, other_code)| other_code != code)
+            .map(|(p, c)| (p.as_str(), c.as_str()))
+            .filter(|pair| seen.insert(*pair))
+            .collect();
+
+        Some(LabelMatch {
+            prefix,
+            code,
+            conflicts,
+        })
+    }
+}
@@ -1,7 +1,6 @@
 #![cfg_attr(target_os = "windows", windows_subsystem = "windows")]
 use eframe::{self, NativeOptions, CreationContext, IconData};
 use log::info;
-use env_logger;
 use anyhow::Result;
 
 mod app;
@@ -10,6 +9,23 @@ mod parser;
 mod model;
 mod labelcodes;
 mod export;
+mod audiometa;
+mod cache;
+mod diagnostic;
+mod edl;
+mod enrich;
+mod filename_rules;
+mod history;
+mod nle_xml;
+mod otio;
+mod production;
+mod project;
+mod protools;
+mod report;
+mod settings;
+mod textfile;
+mod timecode;
+mod worker;
 
 fn main() -> Result<()> {
     env_logger::init();
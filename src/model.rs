@@ -1,11 +1,331 @@
-use serde::{Deserialize, Serialize};
+use crate::timecode::Timecode;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
 
 /// Struktur zur Speicherung der extrahierten Track-Informationen.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrackInfo {
+    /// Zeile innerhalb der Datei, Schlüssel der Handänderungen (siehe `row_key`)
+    #[serde(default)]
+    pub key: String,
     pub index: String,
     pub titel: String,
     pub kuenstler: String,
-    pub duration: Option<f64>, // Dauer in Sekunden
+    pub duration: Option<Timecode>, // hörbare Dauer (überlappende Verwendungen zusammengefasst)
     pub label_code: String,    // Labelcode
+    #[serde(default)]
+    pub isrc: String,
+    #[serde(default)]
+    pub usages: Vec<Usage>,    // einzelne Verwendungen in der Timeline
+    #[serde(default)]
+    pub versions: Vec<String>, // verwendete Versionen des Werks (z.B. "30sec", "stem_bass")
+    #[serde(default)]
+    pub first_record_in: Option<Timecode>, // erste Verwendung in der Timeline
+    #[serde(default)]
+    pub last_record_out: Option<Timecode>, // Ende der letzten Verwendung
+    #[serde(default)]
+    pub rights_holders: Vec<RightsHolder>, // Komponisten, Textdichter, Bearbeiter, Verlage
+    /// Stand vor dem Datenbank-Abgleich; fehlt bei Ergebnissen älterer Versionen.
+    #[serde(default)]
+    pub parsed: Option<ParsedFields>,
+}
+
+/// Felder, die der Datenbank-Abgleich überschreibt, so wie sie eingelesen wurden. Damit
+/// lässt sich ein Index nach einer Datenbank-Änderung neu abgleichen, ohne die Datei zu lesen.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParsedFields {
+    pub titel: String,
+    pub kuenstler: String,
+    pub label_code: String,
+    pub rights_holders: Vec<RightsHolder>,
+}
+
+impl TrackInfo {
+    /// Schlüssel einer Zeile: der Index, ab dem zweiten Track mit demselben Index in einer
+    /// Datei (z.B. mit anderem Titel im Dateinamen) mit laufender Nummer ("abc_123#2").
+    /// Handänderungen gelten je Zeile.
+    pub fn row_key(index: &str, occurrence: usize) -> String {
+        if occurrence <= 1 {
+            index.to_string()
+        } else {
+            format!("{}#{}", index, occurrence)
+        }
+    }
+
+    /// Erstes Record-In und letztes Record-Out aus den Verwendungen neu bestimmen.
+    pub fn update_span(&mut self) {
+        self.first_record_in = self.usages.iter().filter_map(|u| u.record_in).min_by_key(|tc| tc.frames);
+        self.last_record_out = self.usages.iter().filter_map(|u| u.record_out()).max_by_key(|tc| tc.frames);
+    }
+
+    /// Merkt sich die eingelesenen Werte, bevor die Datenbank sie überschreibt.
+    pub fn remember_parsed(&mut self) {
+        self.parsed = Some(ParsedFields {
+            titel: self.titel.clone(),
+            kuenstler: self.kuenstler.clone(),
+            label_code: self.label_code.clone(),
+            rights_holders: self.rights_holders.clone(),
+        });
+    }
+
+    /// Setzt die vom Abgleich überschriebenen Felder auf den eingelesenen Stand zurück.
+    /// Gibt zurück, ob dieser Stand bekannt war.
+    pub fn restore_parsed(&mut self) -> bool {
+        let Some(parsed) = &self.parsed else {
+            return false;
+        };
+        self.titel = parsed.titel.clone();
+        self.kuenstler = parsed.kuenstler.clone();
+        self.label_code = parsed.label_code.clone();
+        self.rights_holders = parsed.rights_holders.clone();
+        true
+    }
+
+    pub fn versions_label(&self) -> String {
+        self.versions.join(", ")
+    }
+
+    /// Alle Beteiligten einer Rolle für die Ausgabe, mit "; " getrennt.
+    pub fn rights_label(&self, role: Role) -> String {
+        self.rights_holders
+            .iter()
+            .filter(|holder| holder.role == role)
+            .map(|holder| holder.to_string())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Rolle eines Beteiligten in der Musikfolge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+    Composer,
+    Lyricist,
+    Arranger,
+    Publisher,
+}
+
+impl Role {
+    pub const ALL: [Role; 4] = [Role::Composer, Role::Lyricist, Role::Arranger, Role::Publisher];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Role::Composer => "Komponist",
+            Role::Lyricist => "Textdichter",
+            Role::Arranger => "Bearbeiter",
+            Role::Publisher => "Verlag",
+        }
+    }
+
+    /// Schlüssel in der Datenbank-Spalte `role`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Role::Composer => "composer",
+            Role::Lyricist => "lyricist",
+            Role::Arranger => "arranger",
+            Role::Publisher => "publisher",
+        }
+    }
+
+    /// Liest den Schlüssel oder die deutsche Bezeichnung (ohne Groß-/Kleinschreibung).
+    pub fn from_key(key: &str) -> Option<Role> {
+        let key = key.trim();
+        Role::ALL
+            .into_iter()
+            .find(|role| role.key().eq_ignore_ascii_case(key) || role.label().eq_ignore_ascii_case(key))
+    }
+}
+
+/// Ein Beteiligter an einem Werk mit optionaler IPI-/GEMA-Mitgliedsnummer und Anteil.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RightsHolder {
+    pub role: Role,
+    pub name: String,
+    #[serde(default)]
+    pub ipi: String,
+    /// Anteil in Prozent; `None` = nicht angegeben.
+    #[serde(default)]
+    pub share: Option<f64>,
+}
+
+impl RightsHolder {
+    pub fn new(role: Role, name: impl Into<String>) -> Self {
+        Self { role, name: name.into(), ipi: String::new(), share: None }
+    }
+}
+
+impl fmt::Display for RightsHolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        let mut details = Vec::new();
+        if !self.ipi.is_empty() {
+            details.push(format!("IPI {}", self.ipi));
+        }
+        if let Some(share) = self.share {
+            details.push(format!("{} %", share));
+        }
+        if !details.is_empty() {
+            write!(f, " ({})", details.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Prüft, dass die Anteile je Rolle zusammen höchstens 100 % ergeben.
+pub fn share_errors(holders: &[RightsHolder]) -> Vec<String> {
+    Role::ALL
+        .into_iter()
+        .filter_map(|role| {
+            let sum: f64 = holders.iter().filter(|h| h.role == role).filter_map(|h| h.share).sum();
+            (sum > 100.0 + 1e-6).then(|| format!("Anteile {} ergeben {} % (mehr als 100 %)", role.label(), sum))
+        })
+        .collect()
+}
+
+/// Eine Verwendung eines Tracks in der Timeline.
+/// Ohne Record-In (z.B. bei einfachen Tracklisten) zählt nur die Dauer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Usage {
+    pub record_in: Option<Timecode>,
+    pub duration: Timecode,
+    /// Version(en) dieser Verwendung; leer = Hauptversion.
+    #[serde(default, alias = "version", deserialize_with = "deserialize_versions")]
+    pub versions: Vec<String>,
+}
+
+/// Ältere Projekte speichern die Versionen einer Verwendung als Text ("alt, 30sec").
+fn deserialize_versions<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Versions {
+        List(Vec<String>),
+        Joined(String),
+    }
+    Ok(match Versions::deserialize(deserializer)? {
+        Versions::List(versions) => versions,
+        Versions::Joined(text) => text.split(", ").filter(|v| !v.is_empty()).map(String::from).collect(),
+    })
+}
+
+impl Usage {
+    pub fn record_out(&self) -> Option<Timecode> {
+        self.record_in.map(|tc_in| tc_in + self.duration)
+    }
+}
+
+/// Von Hand geänderte Felder eines Tracks. Sie werden nach Einlesen und
+/// Datenbank-Abgleich angewendet und bleiben daher bei jedem Durchlauf erhalten.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrackOverride {
+    pub titel: Option<String>,
+    pub kuenstler: Option<String>,
+    pub label_code: Option<String>,
+    pub isrc: Option<String>,
+    pub rights_holders: Option<Vec<RightsHolder>>,
+    /// Gemeldete Dauer; wird anteilig auf die einzelnen Verwendungen verteilt.
+    pub duration: Option<Timecode>,
+}
+
+impl TrackOverride {
+    pub fn apply(&self, track: &mut TrackInfo) {
+        if let Some(titel) = &self.titel {
+            track.titel = titel.clone();
+        }
+        if let Some(kuenstler) = &self.kuenstler {
+            track.kuenstler = kuenstler.clone();
+        }
+        if let Some(label_code) = &self.label_code {
+            track.label_code = label_code.clone();
+        }
+        if let Some(isrc) = &self.isrc {
+            track.isrc = isrc.clone();
+        }
+        if let Some(rights_holders) = &self.rights_holders {
+            track.rights_holders = rights_holders.clone();
+        }
+        if let Some(duration) = self.duration {
+            track.duration = Some(duration);
+            scale_usages(&mut track.usages, duration);
+            track.update_span();
+        }
+    }
+
+    /// Verwirft Felder, die dem eingelesenen Stand entsprechen.
+    pub fn drop_unchanged(&mut self, original: &TrackInfo) {
+        fn keep<T: PartialEq>(value: &mut Option<T>, original: &T) {
+            if value.as_ref() == Some(original) {
+                *value = None;
+            }
+        }
+        keep(&mut self.titel, &original.titel);
+        keep(&mut self.kuenstler, &original.kuenstler);
+        keep(&mut self.label_code, &original.label_code);
+        keep(&mut self.isrc, &original.isrc);
+        keep(&mut self.rights_holders, &original.rights_holders);
+        if self.duration.is_some() && self.duration == original.duration {
+            self.duration = None;
+        }
+    }
+
+    pub fn is_set(&self, field: TrackField) -> bool {
+        match field {
+            TrackField::Titel => self.titel.is_some(),
+            TrackField::Kuenstler => self.kuenstler.is_some(),
+            TrackField::LabelCode => self.label_code.is_some(),
+            TrackField::Duration => self.duration.is_some(),
+        }
+    }
+
+    /// Verwirft die Handänderung eines Feldes.
+    pub fn clear(&mut self, field: TrackField) {
+        match field {
+            TrackField::Titel => self.titel = None,
+            TrackField::Kuenstler => self.kuenstler = None,
+            TrackField::LabelCode => self.label_code = None,
+            TrackField::Duration => self.duration = None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Verteilt eine von Hand gesetzte Dauer anteilig auf die Verwendungen. Anzahl, In-Punkte
+/// und Versionen bleiben erhalten; Rundungsreste landen in der letzten Verwendung.
+fn scale_usages(usages: &mut [Usage], duration: Timecode) {
+    let Some(rate) = usages.first().map(|u| u.duration.rate) else {
+        return;
+    };
+    let target = i128::from(duration.convert(rate).frames);
+    let frames: Vec<i128> = usages.iter().map(|u| i128::from(u.duration.convert(rate).frames)).collect();
+    let total: i128 = frames.iter().sum();
+    let (mut done, mut assigned) = (0, 0);
+    for (usage, frames) in usages.iter_mut().zip(frames) {
+        done += frames;
+        // ohne Dauer der Verwendungen bekommt die erste alles
+        let end = if total > 0 { (target * done + total / 2) / total } else { target };
+        usage.duration = Timecode::from_frames((end - assigned) as i64, rate);
+        assigned = end;
+    }
+}
+
+/// Felder, die direkt in der Track-Übersicht bearbeitet werden können.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackField {
+    Titel,
+    Kuenstler,
+    LabelCode,
+    Duration,
+}
+
+impl TrackField {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TrackField::Titel => "Titel",
+            TrackField::Kuenstler => "Künstler",
+            TrackField::LabelCode => "Labelcode",
+            TrackField::Duration => "Dauer",
+        }
+    }
 }
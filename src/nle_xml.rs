@@ -0,0 +1,371 @@
+use crate::diagnostic::{Diagnostic, DiagnosticKind};
+use crate::parser::{add_track_event, resolve_frame_rate, ParseContext, TrackEvent};
+use crate::timecode::{FrameRate, Timecode};
+use anyhow::{anyhow, bail, Context, Result};
+use log::info;
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Clip-Elemente, die in FCPXML auf einer Audiospur liegen können.
+const FCPXML_CLIPS: [&str; 4] = ["asset-clip", "clip", "audio", "ref-clip"];
+
+/// Liest Final Cut Pro XML (.fcpxml / .fcpxmld) oder Premiere/FCP7 XML (xmeml).
+/// Der Dialekt wird am Wurzelelement erkannt.
+pub fn parse_xml_file(ctx: &mut ParseContext, path: &str) -> Result<()> {
+    let xml_path = Path::new(path);
+    // .fcpxmld ist ein Bundle-Ordner mit der eigentlichen Datei "Info.fcpxml"
+    let content = if xml_path.is_dir() {
+        fs::read_to_string(xml_path.join("Info.fcpxml"))
+            .with_context(|| format!("Keine Info.fcpxml in '{}' gefunden.", path))?
+    } else {
+        fs::read_to_string(xml_path)?
+    };
+
+    let doc = Document::parse(&content).context("Ungültiges XML")?;
+    match doc.root_element().tag_name().name() {
+        "fcpxml" => parse_fcpxml(ctx, path, &doc),
+        "xmeml" => parse_xmeml(ctx, path, &doc),
+        other => bail!("Unbekanntes XML-Format <{}> (erwartet fcpxml oder xmeml)", other),
+    }
+}
+
+/// Final Cut Pro X: Zeiten sind Brüche ("1001/30000s"), die Bildrate steht im
+/// <format> der Sequenz. Als Musik zählen verbundene Clips auf negativen Lanes
+/// sowie Clips, deren Asset kein Video enthält.
+fn parse_fcpxml(ctx: &mut ParseContext, path: &str, doc: &Document) -> Result<()> {
+    let resources: HashMap<&str, Node> = doc
+        .descendants()
+        .filter(|n| n.has_tag_name("format") || n.has_tag_name("asset") || n.has_tag_name("media"))
+        .filter_map(|n| n.attribute("id").map(|id| (id, n)))
+        .collect();
+
+    let sequences: Vec<Node> = doc.descendants().filter(|n| n.has_tag_name("sequence")).collect();
+    if sequences.is_empty() {
+        bail!("Keine <sequence> in der FCPXML-Datei gefunden.");
+    }
+
+    for sequence in sequences {
+        let declared = sequence
+            .attribute("format")
+            .and_then(|id| resources.get(id))
+            .and_then(|format| format.attribute("frameDuration"))
+            .and_then(rate_from_frame_duration)
+            .map(|rate| rate.with_drop_frame(sequence.attribute("tcFormat") == Some("DF")));
+        let rate = resolve_frame_rate(ctx, path, declared);
+
+        for clip in sequence.descendants().filter(|n| is_audio_clip(*n, &resources)) {
+            // <audio> innerhalb eines bereits gezählten Clips nicht doppelt werten
+            if clip.ancestors().skip(1).any(|a| is_audio_clip(a, &resources)) {
+                continue;
+            }
+            let asset = clip.attribute("ref").and_then(|id| resources.get(id));
+
+            let Some(duration) = clip
+                .attribute("duration")
+                .and_then(parse_rational_time)
+                .map(|(num, den)| Timecode::from_rational_seconds(num, den, rate))
+            else {
+                let msg = format!("FCPXML: Clip ohne gültige Dauer übersprungen: {}", clip.attribute("name").unwrap_or("?"));
+                let (number, raw) = source_line(doc, clip);
+                ctx.report(Diagnostic::warning(DiagnosticKind::InvalidTimecode, msg).file(path).line(number, raw));
+                continue;
+            };
+            if duration.frames <= 0 {
+                continue;
+            }
+
+            let clip_name = clip
+                .attribute("name")
+                .or_else(|| asset.and_then(|a| a.attribute("name")))
+                .or_else(|| asset.and_then(|a| a.attribute("src")).map(file_name_from_url))
+                .unwrap_or_default()
+                .to_string();
+
+            let record_in = timeline_position(clip).map(|(num, den)| Timecode::from_rational_seconds(num, den, rate));
+
+            info!("FCPXML Audio-Clip: {} ({})", clip_name, duration);
+            add_track_event(ctx, path, TrackEvent::new(clip_name, duration).at(record_in));
+        }
+    }
+
+    Ok(())
+}
+
+/// Premiere Pro / FCP7 (xmeml): Zeiten sind ganze Frames in der <timebase> der Sequenz.
+/// Es werden nur <clipitem>-Elemente unter <media><audio> ausgewertet.
+fn parse_xmeml(ctx: &mut ParseContext, path: &str, doc: &Document) -> Result<()> {
+    let sequences: Vec<Node> = doc.descendants().filter(|n| n.has_tag_name("sequence")).collect();
+    if sequences.is_empty() {
+        bail!("Keine <sequence> in der XML-Datei gefunden.");
+    }
+
+    for sequence in sequences {
+        let sequence_rate = child(sequence, "rate")
+            .and_then(xmeml_rate)
+            .ok_or_else(|| anyhow!("Sequenz ohne gültige <rate>"))?;
+        let drop_frame = child(sequence, "timecode")
+            .and_then(|tc| child_text(tc, "displayformat"))
+            .is_some_and(|f| f.eq_ignore_ascii_case("DF"));
+        let sequence_rate = sequence_rate.with_drop_frame(drop_frame);
+        let rate = resolve_frame_rate(ctx, path, Some(sequence_rate));
+
+        let Some(audio) = child(sequence, "media").and_then(|m| child(m, "audio")) else {
+            continue;
+        };
+
+        for track in audio.children().filter(|n| n.has_tag_name("track")) {
+            if child_text(track, "enabled").is_some_and(|e| e.eq_ignore_ascii_case("FALSE")) {
+                continue;
+            }
+
+            for clip in track.children().filter(|n| n.has_tag_name("clipitem")) {
+                if child_text(clip, "enabled").is_some_and(|e| e.eq_ignore_ascii_case("FALSE")) {
+                    continue;
+                }
+
+                let frame = |name: &str| child_text(clip, name).and_then(|v| v.parse::<i64>().ok());
+                // start/end sind -1, wenn der Clip an einer Blende beginnt/endet
+                let (record_in, frames) = match (frame("start"), frame("end"), frame("in"), frame("out")) {
+                    (Some(start), Some(end), _, _) if start >= 0 && end >= 0 => (Some(start), end - start),
+                    (start, end, Some(src_in), Some(src_out)) => {
+                        let frames = src_out - src_in;
+                        // An einer Blende liegt nur eine Seite fest
+                        let record_in = match (start, end) {
+                            (Some(start), _) if start >= 0 => Some(start),
+                            (_, Some(end)) if end >= 0 => Some(end - frames),
+                            _ => None,
+                        };
+                        (record_in, frames)
+                    }
+                    _ => {
+                        let msg = format!("XML: Clip ohne gültige Dauer übersprungen: {}", child_text(clip, "name").unwrap_or("?"));
+                        let (number, raw) = source_line(doc, clip);
+                        ctx.report(Diagnostic::warning(DiagnosticKind::InvalidTimecode, msg).file(path).line(number, raw));
+                        continue;
+                    }
+                };
+                if frames <= 0 {
+                    continue;
+                }
+                let duration = Timecode::from_frames(frames, sequence_rate).convert(rate);
+                let record_in = record_in.map(|f| Timecode::from_frames(f, sequence_rate).convert(rate));
+
+                let clip_name = child_text(clip, "name")
+                    .or_else(|| child(clip, "file").and_then(|f| child_text(f, "name")))
+                    .unwrap_or_default()
+                    .to_string();
+
+                info!("XML Audio-Clip: {} ({})", clip_name, duration);
+                add_track_event(ctx, path, TrackEvent::new(clip_name, duration).at(record_in));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Ein Clip zählt als Musik, wenn er auf einer negativen Lane liegt oder sein Asset kein
+/// Video hat. Der Originalton eines Videoclips (auch als eigenes `<audio>`) zählt nicht.
+fn is_audio_clip(node: Node, resources: &HashMap<&str, Node>) -> bool {
+    if !FCPXML_CLIPS.contains(&node.tag_name().name()) {
+        return false;
+    }
+    let on_audio_lane = node
+        .attribute("lane")
+        .and_then(|l| l.parse::<i32>().ok())
+        .is_some_and(|lane| lane < 0);
+    let audio_only = node
+        .attribute("ref")
+        .and_then(|id| resources.get(id))
+        .is_some_and(|asset| is_audio_only_asset(*asset, resources));
+    on_audio_lane || audio_only
+}
+
+/// Asset ohne Video: `hasVideo="0"` oder, ohne Angabe, Ton mit einem Format ohne Bildgröße.
+fn is_audio_only_asset(asset: Node, resources: &HashMap<&str, Node>) -> bool {
+    if !asset.has_tag_name("asset") {
+        return false;
+    }
+    match asset.attribute("hasVideo") {
+        Some(has_video) => has_video == "0",
+        None => {
+            asset.attribute("hasAudio") == Some("1")
+                && asset
+                    .attribute("format")
+                    .and_then(|id| resources.get(id))
+                    .is_none_or(|format| format.attribute("width").is_none())
+        }
+    }
+}
+
+/// Position eines Clips in der Sequenz als Bruch in Sekunden. Verbundene Clips
+/// liegen in der lokalen Zeit ihres Eltern-Clips (offset + (t - start)), das
+/// wird bis zur <sequence> (inkl. tcStart) hochgerechnet.
+fn timeline_position(clip: Node) -> Option<(i64, i64)> {
+    let offset = rational_attribute(clip, "offset");
+    to_sequence_time(clip.parent_element()?, offset)
+}
+
+fn to_sequence_time(container: Node, time: (i64, i64)) -> Option<(i64, i64)> {
+    match container.tag_name().name() {
+        "sequence" => Some(add_rational(time, rational_attribute(container, "tcStart"))),
+        "spine" if container.attribute("offset").is_none() => to_sequence_time(container.parent_element()?, time),
+        "spine" => {
+            // Nebenhandlung: beginnt beim ersten enthaltenen Clip
+            let start = container
+                .children()
+                .find_map(|c| c.attribute("offset").and_then(parse_rational_time))
+                .unwrap_or((0, 1));
+            let local = add_rational(rational_attribute(container, "offset"), sub_rational(time, start));
+            to_sequence_time(container.parent_element()?, local)
+        }
+        _ => {
+            let local = add_rational(
+                rational_attribute(container, "offset"),
+                sub_rational(time, rational_attribute(container, "start")),
+            );
+            to_sequence_time(container.parent_element()?, local)
+        }
+    }
+}
+
+fn rational_attribute(node: Node, name: &str) -> (i64, i64) {
+    node.attribute(name).and_then(parse_rational_time).unwrap_or((0, 1))
+}
+
+fn add_rational((a_num, a_den): (i64, i64), (b_num, b_den): (i64, i64)) -> (i64, i64) {
+    let num = a_num * b_den + b_num * a_den;
+    let den = a_den * b_den;
+    let divisor = gcd(num, den).max(1);
+    (num / divisor, den / divisor)
+}
+
+fn sub_rational(a: (i64, i64), (b_num, b_den): (i64, i64)) -> (i64, i64) {
+    add_rational(a, (-b_num, b_den))
+}
+
+/// Zeilennummer und Zeile, in der ein Element beginnt (für Meldungen).
+fn source_line<'a>(doc: &'a Document, node: Node) -> (usize, &'a str) {
+    let row = doc.text_pos_at(node.range().start).row as usize;
+    (row, doc.input_text().lines().nth(row.saturating_sub(1)).unwrap_or_default())
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|n| n.text()).map(|t| t.trim())
+}
+
+/// <rate><timebase>30</timebase><ntsc>TRUE</ntsc></rate> => 29,97
+fn xmeml_rate(rate: Node) -> Option<FrameRate> {
+    let timebase = child_text(rate, "timebase")?.parse::<u32>().ok()?;
+    let ntsc = child_text(rate, "ntsc").is_some_and(|n| n.eq_ignore_ascii_case("TRUE"));
+    Some(if ntsc {
+        FrameRate::new(timebase * 1000, 1001, false)
+    } else {
+        FrameRate::new(timebase, 1, false)
+    })
+}
+
+/// "100/2500s" => 25 fps, "1001/30000s" => 29,97 fps
+fn rate_from_frame_duration(frame_duration: &str) -> Option<FrameRate> {
+    let (num, den) = parse_rational_time(frame_duration)?;
+    if num <= 0 {
+        return None;
+    }
+    let divisor = gcd(num, den);
+    Some(FrameRate::new((den / divisor) as u32, (num / divisor) as u32, false))
+}
+
+/// FCPXML-Zeitwerte: "3600s", "1001/30000s" oder "0s". Liefert (Zähler, Nenner) in Sekunden.
+fn parse_rational_time(value: &str) -> Option<(i64, i64)> {
+    let value = value.trim().strip_suffix('s')?;
+    match value.split_once('/') {
+        Some((num, den)) => {
+            let den = den.parse::<i64>().ok()?;
+            (den > 0).then_some((num.parse::<i64>().ok()?, den))
+        }
+        None => Some((value.parse::<i64>().ok()?, 1)),
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// "file:///Volumes/Musik/ANW1832_001.wav" => "ANW1832_001.wav"
+fn file_name_from_url(url: &str) -> &str {
+    url.rsplit('/').next().unwrap_or(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TrackInfo;
+
+    const FCPXML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<fcpxml version="1.10">
+    <resources>
+        <format id="r1" frameDuration="1/25s" width="1920" height="1080"/>
+        <asset id="r2" name="Interview" hasVideo="1" hasAudio="1" format="r1"/>
+        <asset id="r3" name="ANW1832_001_Morning_Light.wav" hasAudio="1"/>
+        <asset id="r4" name="ANW1832_002_Evening_Shade.wav" hasVideo="0" hasAudio="1"/>
+    </resources>
+    <library>
+        <event>
+            <project name="Test">
+                <sequence format="r1" tcStart="0s">
+                    <spine>
+                        <asset-clip ref="r2" offset="0s" start="10s" duration="20s" name="Interview">
+                            <asset-clip ref="r3" lane="-1" offset="12s" start="0s" duration="5s"/>
+                        </asset-clip>
+                        <clip offset="20s" duration="10s" name="B-Roll">
+                            <video ref="r2" offset="0s" duration="10s"/>
+                            <audio ref="r2" offset="0s" duration="10s"/>
+                        </clip>
+                        <asset-clip ref="r4" offset="30s" duration="4s"/>
+                    </spine>
+                </sequence>
+            </project>
+        </event>
+    </library>
+</fcpxml>"#;
+
+    fn parse(xml: &str) -> Vec<TrackInfo> {
+        let mut ctx = ParseContext::for_tests();
+        let doc = Document::parse(xml).unwrap();
+        parse_fcpxml(&mut ctx, "test.fcpxml", &doc).unwrap();
+        ctx.tracks_per_file.remove("test.fcpxml").unwrap_or_default()
+    }
+
+    #[test]
+    fn connected_audio_clip_is_placed_in_sequence_time() {
+        let tracks = parse(FCPXML);
+        let track = tracks.iter().find(|t| t.index.starts_with("anw1832_001")).unwrap();
+        // verbundener Clip: 0s + (12s - 10s) = 2s
+        assert_eq!(track.first_record_in.unwrap().frames, 50);
+        assert_eq!(track.duration.unwrap().frames, 125);
+    }
+
+    #[test]
+    fn audio_only_asset_on_the_spine_counts() {
+        let tracks = parse(FCPXML);
+        let track = tracks.iter().find(|t| t.index.starts_with("anw1832_002")).unwrap();
+        assert_eq!(track.first_record_in.unwrap().frames, 750);
+    }
+
+    #[test]
+    fn sync_sound_of_video_clips_is_ignored() {
+        let tracks = parse(FCPXML);
+        assert_eq!(tracks.len(), 2, "{:?}", tracks.iter().map(|t| &t.index).collect::<Vec<_>>());
+        assert!(tracks.iter().all(|t| !t.index.contains("interview") && !t.index.contains("b-roll")));
+    }
+}
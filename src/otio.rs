@@ -0,0 +1,331 @@
+use crate::diagnostic::{Diagnostic, DiagnosticKind};
+use crate::parser::{add_track_event, resolve_frame_rate, ParseContext, TrackEvent};
+use crate::timecode::{FrameRate, Timecode};
+use anyhow::{bail, Context, Result};
+use log::info;
+use serde_json::Value;
+use std::fs::File;
+use std::io::BufReader;
+
+/// Höhere Raten sind Abtastraten von Audio-Clips (z.B. 48000), keine Bildraten.
+const MAX_FRAME_RATE: f64 = 120.0;
+
+/// Sekunden als gekürzter Bruch (Zähler, Nenner). Positionen werden so addiert und erst
+/// am Ende in Frames umgerechnet, damit lange Timelines bei 29,97/23,976 nicht wandern.
+type Seconds = (i64, i64);
+
+/// Liest eine OpenTimelineIO-Datei (.otio, JSON).
+/// Alle Clips auf Spuren mit `"kind": "Audio"` werden zu Tracks; der Name der
+/// Medienreferenz läuft wie ein Dateiname durch `parse_track_filename`.
+pub fn parse_otio_file(ctx: &mut ParseContext, path: &str) -> Result<()> {
+    let file = File::open(path)?;
+    let root: Value = serde_json::from_reader(BufReader::new(file)).context("Ungültiges OTIO-JSON")?;
+    parse_otio(ctx, path, &root)
+}
+
+fn parse_otio(ctx: &mut ParseContext, path: &str, root: &Value) -> Result<()> {
+    if !schema_of(root).starts_with("Timeline.") {
+        bail!("Keine OTIO-Timeline (OTIO_SCHEMA = '{}')", schema_of(root));
+    }
+
+    let mut audio_clips = Vec::new();
+    collect_audio_clips(&root["tracks"], false, None, &mut audio_clips);
+
+    // Bildrate der Timeline: global_start_time, sonst ein Videoclip, sonst ein
+    // Audioclip mit Bildrate statt Abtastrate
+    let declared = root["global_start_time"]["rate"]
+        .as_f64()
+        .filter(|rate| is_frame_rate(*rate))
+        .or_else(|| video_frame_rate(&root["tracks"], false))
+        .or_else(|| {
+            audio_clips
+                .iter()
+                .filter_map(|(clip, _)| clip_duration(clip))
+                .map(|(_, rate)| rate)
+                .find(|rate| is_frame_rate(*rate))
+        })
+        .map(frame_rate_from_otio);
+    let rate = resolve_frame_rate(ctx, path, declared);
+    let global_start = root["global_start_time"]["value"]
+        .as_f64()
+        .zip(root["global_start_time"]["rate"].as_f64())
+        .filter(|(_, rate)| *rate > 0.0)
+        .map(|(value, rate)| rational_seconds(value, rate))
+        .unwrap_or((0, 1));
+
+    for (clip, position) in audio_clips {
+        if clip["enabled"].as_bool() == Some(false) {
+            continue;
+        }
+
+        let Some((value, clip_rate)) = clip_duration(clip) else {
+            let msg = format!("OTIO: Clip ohne Dauer übersprungen: {}", clip["name"].as_str().unwrap_or("?"));
+            ctx.report(Diagnostic::warning(DiagnosticKind::InvalidTimecode, msg).file(path));
+            continue;
+        };
+        let (num, den) = rational_seconds(value, clip_rate);
+        let duration = Timecode::from_rational_seconds(num, den, rate);
+        if duration.frames <= 0 {
+            continue;
+        }
+
+        let clip_name = media_reference_name(clip)
+            .or_else(|| clip["name"].as_str().map(|s| s.to_string()))
+            .unwrap_or_default();
+
+        let record_in = position.map(|seconds| {
+            let (num, den) = add_seconds(global_start, seconds);
+            Timecode::from_rational_seconds(num, den, rate)
+        });
+
+        info!("OTIO Audio-Clip: {} ({})", clip_name, duration);
+        add_track_event(ctx, path, TrackEvent::new(clip_name, duration).at(record_in));
+    }
+
+    Ok(())
+}
+
+fn schema_of(value: &Value) -> &str {
+    value["OTIO_SCHEMA"].as_str().unwrap_or_default()
+}
+
+/// Läuft rekursiv durch Stacks und Tracks und sammelt Clips auf Audiospuren
+/// zusammen mit ihrer Startposition in der Timeline. Innerhalb eines
+/// Tracks liegen Clips und Gaps lückenlos hintereinander; Blenden belegen keine Zeit.
+fn collect_audio_clips<'a>(
+    node: &'a Value,
+    in_audio_track: bool,
+    position: Option<Seconds>,
+    clips: &mut Vec<(&'a Value, Option<Seconds>)>,
+) {
+    let schema = schema_of(node);
+    if schema.starts_with("Clip.") {
+        if in_audio_track {
+            clips.push((node, position));
+        }
+        return;
+    }
+
+    let in_audio_track = if schema.starts_with("Track.") {
+        if node["enabled"].as_bool() == Some(false) {
+            return;
+        }
+        node["kind"].as_str() == Some("Audio")
+    } else {
+        in_audio_track
+    };
+
+    let Some(children) = node["children"].as_array() else {
+        return;
+    };
+    if schema.starts_with("Track.") {
+        let mut position = position;
+        for child in children {
+            collect_audio_clips(child, in_audio_track, position, clips);
+            if !schema_of(child).starts_with("Transition.") {
+                position = position.zip(item_duration(child)).map(|(p, d)| add_seconds(p, d));
+            }
+        }
+    } else {
+        // Stacks (und die Timeline selbst) beginnen alle Kinder an derselben Stelle
+        let position = if schema.starts_with("Stack.") { position.or(Some((0, 1))) } else { position };
+        for child in children {
+            collect_audio_clips(child, in_audio_track, position, clips);
+        }
+    }
+}
+
+fn is_frame_rate(rate: f64) -> bool {
+    rate > 0.0 && rate <= MAX_FRAME_RATE
+}
+
+/// Rate des ersten Clips auf einer Videospur.
+fn video_frame_rate(node: &Value, in_video_track: bool) -> Option<f64> {
+    let schema = schema_of(node);
+    if schema.starts_with("Clip.") {
+        return clip_duration(node).map(|(_, rate)| rate).filter(|rate| in_video_track && is_frame_rate(*rate));
+    }
+    let in_video_track = if schema.starts_with("Track.") {
+        node["kind"].as_str() == Some("Video")
+    } else {
+        in_video_track
+    };
+    node["children"]
+        .as_array()?
+        .iter()
+        .find_map(|child| video_frame_rate(child, in_video_track))
+}
+
+/// Länge eines Elements im Track (Clip, Gap, verschachtelter Stack).
+fn item_duration(item: &Value) -> Option<Seconds> {
+    if let Some((value, rate)) = clip_duration(item) {
+        return Some(rational_seconds(value, rate));
+    }
+    let duration = &item["source_range"]["duration"];
+    let rate = duration["rate"].as_f64().filter(|r| *r > 0.0)?;
+    Some(rational_seconds(duration["value"].as_f64()?, rate))
+}
+
+/// Dauer eines Clips als (value, rate): source_range, sonst available_range der Medienreferenz.
+fn clip_duration(clip: &Value) -> Option<(f64, f64)> {
+    let duration = if clip["source_range"].is_object() {
+        &clip["source_range"]["duration"]
+    } else {
+        &active_media_reference(clip)?["available_range"]["duration"]
+    };
+    let value = duration["value"].as_f64()?;
+    let rate = duration["rate"].as_f64()?;
+    (rate > 0.0).then_some((value, rate))
+}
+
+/// Clip.1 hat "media_reference", Clip.2 eine Map "media_references" mit aktivem Schlüssel.
+fn active_media_reference(clip: &Value) -> Option<&Value> {
+    if let Some(references) = clip["media_references"].as_object() {
+        let key = clip["active_media_reference_key"].as_str().unwrap_or("DEFAULT_MEDIA");
+        return references.get(key).or_else(|| references.values().next());
+    }
+    clip.get("media_reference").filter(|r| r.is_object())
+}
+
+fn media_reference_name(clip: &Value) -> Option<String> {
+    let reference = active_media_reference(clip)?;
+    if let Some(name) = reference["name"].as_str().filter(|n| !n.is_empty()) {
+        return Some(name.to_string());
+    }
+    reference["target_url"]
+        .as_str()
+        .and_then(|url| url.rsplit('/').next())
+        .filter(|n| !n.is_empty())
+        .map(|n| n.to_string())
+}
+
+/// OTIO speichert Raten als Gleitkommazahl (23.976023976...). Bekannte Raten
+/// werden auf ihren exakten Bruch abgebildet.
+fn frame_rate_from_otio(rate: f64) -> FrameRate {
+    FrameRate::ALL
+        .iter()
+        .filter(|r| !r.drop_frame)
+        .find(|r| (r.num as f64 / r.den as f64 - rate).abs() < 0.001)
+        .copied()
+        .unwrap_or_else(|| FrameRate::new(rate.round().max(1.0) as u32, 1, false))
+}
+
+/// RationalTime (value/rate) als exakter Bruch in Sekunden. Ganze Frames bei einer bekannten
+/// Rate bzw. einer ganzzahligen Abtastrate sind exakt, Bruchteile auf Nanosekunden genau.
+fn rational_seconds(value: f64, rate: f64) -> Seconds {
+    let source = frame_rate_from_otio(rate);
+    let exact_rate = (source.num as f64 / source.den as f64 - rate).abs() < 0.001;
+    if value.fract() == 0.0 && exact_rate {
+        // value Frames bei num/den fps => value * den / num Sekunden
+        reduce(value as i64 * source.den as i64, source.num as i64)
+    } else {
+        const NANOS: i64 = 1_000_000_000;
+        reduce((value / rate * NANOS as f64).round() as i64, NANOS)
+    }
+}
+
+fn add_seconds((a_num, a_den): Seconds, (b_num, b_den): Seconds) -> Seconds {
+    let den = a_den / gcd(a_den, b_den) * b_den;
+    reduce(a_num * (den / a_den) + b_num * (den / b_den), den)
+}
+
+fn reduce(num: i64, den: i64) -> Seconds {
+    let divisor = gcd(num, den).max(1);
+    (num / divisor, den / divisor)
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TrackInfo;
+
+    fn gap(frames: u32) -> String {
+        format!(
+            r#"{{"OTIO_SCHEMA": "Gap.1", "source_range": {{"OTIO_SCHEMA": "TimeRange.1",
+                "start_time": {{"OTIO_SCHEMA": "RationalTime.1", "value": 0, "rate": 29.97002997002997}},
+                "duration": {{"OTIO_SCHEMA": "RationalTime.1", "value": {frames}, "rate": 29.97002997002997}}}}}}"#
+        )
+    }
+
+    fn clip(name: &str, samples: u32) -> String {
+        format!(
+            r#"{{"OTIO_SCHEMA": "Clip.1", "name": "{name}", "media_reference": {{"OTIO_SCHEMA": "ExternalReference.1",
+                "target_url": "file:///audio/{name}"}}, "source_range": {{"OTIO_SCHEMA": "TimeRange.1",
+                "start_time": {{"OTIO_SCHEMA": "RationalTime.1", "value": 0, "rate": 48000}},
+                "duration": {{"OTIO_SCHEMA": "RationalTime.1", "value": {samples}, "rate": 48000}}}}}}"#
+        )
+    }
+
+    fn track(kind: &str, children: &[String]) -> String {
+        format!(r#"{{"OTIO_SCHEMA": "Track.1", "kind": "{kind}", "children": [{}]}}"#, children.join(","))
+    }
+
+    /// Eine Stunde Startzeit bei 29,97, auf der Audiospur ein verschachtelter Stack
+    /// mit eigener Audiospur und danach ein weiterer Clip.
+    fn timeline() -> String {
+        let nested = format!(
+            r#"{{"OTIO_SCHEMA": "Stack.1", "source_range": {{"OTIO_SCHEMA": "TimeRange.1",
+                "start_time": {{"OTIO_SCHEMA": "RationalTime.1", "value": 0, "rate": 29.97002997002997}},
+                "duration": {{"OTIO_SCHEMA": "RationalTime.1", "value": 600, "rate": 29.97002997002997}}}},
+                "children": [{}]}}"#,
+            track("Audio", &[gap(300), clip("ANW1832_001_Morning_Light.wav", 240240)])
+        );
+        let audio = track("Audio", &[gap(1000), nested, clip("ANW1832_002_Evening_Shade.wav", 48048)]);
+        format!(
+            r#"{{"OTIO_SCHEMA": "Timeline.1", "name": "Test",
+                "global_start_time": {{"OTIO_SCHEMA": "RationalTime.1", "value": 107892, "rate": 29.97002997002997}},
+                "tracks": {{"OTIO_SCHEMA": "Stack.1", "children": [{audio}]}}}}"#
+        )
+    }
+
+    fn parse(json: &str) -> Vec<TrackInfo> {
+        let mut ctx = ParseContext::for_tests();
+        let root: Value = serde_json::from_str(json).unwrap();
+        parse_otio(&mut ctx, "test.otio", &root).unwrap();
+        ctx.tracks_per_file.remove("test.otio").unwrap_or_default()
+    }
+
+    fn track_for<'a>(tracks: &'a [TrackInfo], index: &str) -> &'a TrackInfo {
+        tracks.iter().find(|t| t.index.starts_with(index)).unwrap()
+    }
+
+    #[test]
+    fn clips_in_nested_stacks_are_placed_in_timeline_time() {
+        let tracks = parse(&timeline());
+        let nested = track_for(&tracks, "anw1832_001");
+        // 107892 + 1000 (Gap) + 300 (Gap im Stack)
+        assert_eq!(nested.first_record_in.unwrap().frames, 109_192);
+        // 240240 Samples = 5,005 s = 150 Frames bei 29,97
+        assert_eq!(nested.duration.unwrap().frames, 150);
+    }
+
+    #[test]
+    fn clip_after_a_nested_stack_starts_after_its_duration() {
+        let tracks = parse(&timeline());
+        let after = track_for(&tracks, "anw1832_002");
+        assert_eq!(after.first_record_in.unwrap().frames, 109_492);
+        assert_eq!(after.duration.unwrap().frames, 30);
+    }
+
+    #[test]
+    fn long_timelines_do_not_drift() {
+        // 2000 Gaps à einem Frame bei 29,97 müssen exakt 2000 Frames ergeben
+        let mut children: Vec<String> = (0..2000).map(|_| gap(1)).collect();
+        children.push(clip("ANW1832_003_Late.wav", 48048));
+        let json = format!(
+            r#"{{"OTIO_SCHEMA": "Timeline.1", "global_start_time": {{"OTIO_SCHEMA": "RationalTime.1", "value": 0, "rate": 29.97002997002997}},
+                "tracks": {{"OTIO_SCHEMA": "Stack.1", "children": [{}]}}}}"#,
+            track("Audio", &children)
+        );
+        let tracks = parse(&json);
+        assert_eq!(track_for(&tracks, "anw1832_003").first_record_in.unwrap().frames, 2000);
+    }
+}
@@ -1,106 +1,527 @@
-use crate::model::TrackInfo;
+use crate::model::{RightsHolder, Role, TrackInfo, Usage};
 use crate::app::GemaLauncherApp;
+use crate::audiometa::{self, AudioMetadata};
+use crate::diagnostic::{push_diagnostic, Diagnostic, DiagnosticKind};
+use crate::edl;
+use crate::filename_rules::{FilenameRules, RuleMatch};
+use crate::labelcodes::LabelCodes;
+use crate::nle_xml;
+use crate::otio;
+use crate::protools;
+use crate::settings::{Settings, TextColumnMapping};
+use crate::textfile;
+use crate::timecode::{detect_frame_rate, FrameRate, Timecode};
 use anyhow::Result;
-use log::{info, error};
+use log::info;
 use regex::Regex;
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::{Arc, LazyLock};
 
-pub fn parse_all_files(app: &mut GemaLauncherApp) -> Result<()> {
-    app.tracks_per_file.clear();
-    app.error_messages.clear();
+/// Timecode mit Drop-Frame-Trenner ';' vor den Frames
+static DROP_FRAME_TC_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d{2}:\d{2}:\d{2};\d{2}").unwrap());
+/// Bis zu 2 `_Ziffern_`-Blöcke als "index"
+static INDEX_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?P<index>.*?_\d+_(?:\d+_)?)(?P<rest>.*)$").unwrap());
 
-    let filenames_clone = app.filenames.clone();
+/// Alles, was die Parser lesen und schreiben. Wird aus dem App-Zustand kopiert,
+/// damit das Einlesen in einem eigenen Thread laufen kann (siehe `worker`).
+pub struct ParseContext {
+    pub settings: Settings,
+    pub label_codes: Arc<LabelCodes>,
+    pub filename_rules: Arc<FilenameRules>,
+    pub frame_rate_overrides: HashMap<String, FrameRate>,
 
-    for filename in filenames_clone {
-        let path = Path::new(&filename);
-        if path.extension().and_then(|s| s.to_str()) == Some("txt") {
-            if let Err(e) = parse_text_file(app, &filename) {
-                let msg = format!("Fehler beim Parsen der Textdatei {}: {}", filename, e);
-                app.error_messages.push(msg.clone());
-                error!("{}", msg);
-            }
-        } else {
-            let msg = format!("Datei '{}' ist keine .txt und wird ignoriert.", filename);
-            app.error_messages.push(msg.clone());
-            error!("{}", msg);
+    // Ergebnisse
+    pub tracks_per_file: HashMap<String, Vec<TrackInfo>>,
+    pub detected_frame_rates: HashMap<String, FrameRate>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ParseContext {
+    pub fn from_app(app: &GemaLauncherApp) -> Self {
+        Self {
+            settings: app.settings.clone(),
+            label_codes: Arc::clone(&app.label_codes),
+            filename_rules: Arc::clone(&app.filename_rules),
+            frame_rate_overrides: app.frame_rate_overrides.clone(),
+            tracks_per_file: HashMap::new(),
+            detected_frame_rates: HashMap::new(),
+            // Fehler der Regeldatei bei jedem Einlesen erneut melden
+            diagnostics: app.filename_rules.errors.clone(),
         }
     }
 
-    Ok(())
+    pub fn report(&mut self, diagnostic: Diagnostic) {
+        push_diagnostic(&mut self.diagnostics, diagnostic);
+    }
+}
+
+/// Liest eine Eingabedatei je nach Endung ein. Fehler landen als Meldung im Kontext.
+pub fn parse_file(ctx: &mut ParseContext, filename: &str) {
+    let path = Path::new(filename);
+    let extension = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase());
+
+    let result = match extension.as_deref() {
+        Some("edl") => edl::parse_edl_file(ctx, filename),
+        // Manche Systeme exportieren EDLs als .txt
+        Some("txt") if edl::looks_like_edl(filename) => edl::parse_edl_file(ctx, filename),
+        Some("txt") if protools::looks_like_protools(filename) => protools::parse_protools_file(ctx, filename),
+        Some("txt") => parse_text_file(ctx, filename),
+        Some("fcpxml") | Some("fcpxmld") | Some("xml") => nle_xml::parse_xml_file(ctx, filename),
+        Some("otio") => otio::parse_otio_file(ctx, filename),
+        Some("wav") | Some("wave") | Some("bwf") => audiometa::parse_wav_file(ctx, filename),
+        Some("mp3") => audiometa::parse_mp3_file(ctx, filename),
+        _ => {
+            ctx.report(
+                Diagnostic::warning(DiagnosticKind::UnsupportedFormat, "Kein unterstütztes Format, Datei wird ignoriert.")
+                    .file(filename)
+                    .suggest("Unterstützt: .txt, .edl, .fcpxml, .xml, .otio, .wav, .mp3"),
+            );
+            return;
+        }
+    };
+
+    if let Err(e) = result {
+        // Parser können eine fertige Meldung mit Fundstelle liefern
+        let diagnostic = match e.downcast::<Diagnostic>() {
+            Ok(diagnostic) => diagnostic,
+            Err(e) => Diagnostic::error(DiagnosticKind::File, format!("Fehler beim Parsen: {:#}", e)),
+        };
+        ctx.report(diagnostic.file(filename));
+    }
+
+    if let Some(tracks) = ctx.tracks_per_file.get_mut(filename) {
+        sort_by_program_order(tracks);
+    }
 }
 
-/// Beispielhaftes Einlesen deiner TXT-Datei:
-fn parse_text_file(app: &mut GemaLauncherApp, path: &str) -> Result<()> {
-    let file = File::open(path)?;
-    let reader = io::BufReader::new(file);
+/// Sortiert Tracks nach ihrer ersten Verwendung in der Timeline. Tracks ohne
+/// Position (z.B. aus Tracklisten ohne Startspalte) folgen in Eingabereihenfolge.
+pub(crate) fn sort_by_program_order(tracks: &mut [TrackInfo]) {
+    tracks.sort_by_key(|track| match track.first_record_in {
+        Some(tc) => (0, tc.frames),
+        None => (1, 0),
+    });
+}
+
+/// Bekannte Kopfzeilen der Tracklisten-Spalten (klein geschrieben), in Prioritätsreihenfolge:
+/// Bei "Track\tDuration\tName" gewinnt "name" vor "track".
+const DURATION_COLUMNS: [&str; 5] = ["duration", "dauer", "length", "länge", "laenge"];
+const CLIP_NAME_COLUMNS: [&str; 8] = ["clip name", "clipname", "name", "clip", "track name", "track", "file", "dateiname"];
+const RECORD_IN_COLUMNS: [&str; 6] = ["record in", "rec in", "tc in", "timecode in", "start", "anfang"];
+
+/// Feste Spalten älterer Tracklisten ohne erkennbare Kopfzeile (0-basiert).
+const LEGACY_DURATION_COLUMN: usize = 1;
+const LEGACY_CLIP_NAME_COLUMN: usize = 2;
+
+/// Spaltenpositionen einer Trackliste, aus der Kopfzeile ermittelt.
+struct TextColumns {
+    duration: usize,
+    clip_name: usize,
+    record_in: Option<usize>,
+    /// Kopfzeile nicht erkannt, feste Spalten wie in älteren Versionen
+    legacy: bool,
+}
 
-    let mut lines_iter = reader.lines();
-    // 1. Zeile (Header) überspringen
-    lines_iter.next();
+impl TextColumns {
+    /// Sucht die Spalten erst über die Zuordnung aus den Einstellungen, dann
+    /// über die bekannten Namen. Erkennt die Kopfzeile gar keine Pflichtspalte,
+    /// gelten die festen Spalten älterer Versionen (Dauer = 2., Name = 3. Spalte);
+    /// sonst werden fehlende Pflichtspalten alle zusammen gemeldet.
+    fn from_header(header: &str, mapping: &TextColumnMapping) -> Result<Self> {
+        let names: Vec<String> = header
+            .trim_start_matches('\u{feff}')
+            .split('\t')
+            .map(|c| c.trim().to_lowercase())
+            .collect();
 
-    for line_result in lines_iter {
-        let line = match line_result {
-            Ok(l) => l,
-            Err(_) => continue,
+        let find = |configured: &str, known: &[&str]| -> Option<usize> {
+            let configured = configured.trim().to_lowercase();
+            if !configured.is_empty() {
+                return match configured.parse::<usize>() {
+                    Ok(number) => number.checked_sub(1),
+                    Err(_) => names.iter().position(|n| *n == configured),
+                };
+            }
+            known.iter().find_map(|alias| names.iter().position(|n| n == alias))
         };
+
+        let duration = find(&mapping.duration, &DURATION_COLUMNS);
+        let clip_name = find(&mapping.clip_name, &CLIP_NAME_COLUMNS);
+        let record_in = find(&mapping.record_in, &RECORD_IN_COLUMNS);
+
+        let configured = !mapping.duration.trim().is_empty() || !mapping.clip_name.trim().is_empty();
+        match (duration, clip_name) {
+            (Some(duration), Some(clip_name)) => Ok(Self { duration, clip_name, record_in, legacy: false }),
+            (None, None) if !configured => Ok(Self {
+                duration: LEGACY_DURATION_COLUMN,
+                clip_name: LEGACY_CLIP_NAME_COLUMN,
+                record_in: None,
+                legacy: true,
+            }),
+            _ => {
+                let mut missing = Vec::new();
+                if duration.is_none() {
+                    missing.push(column_description(&mapping.duration, "Dauer", "Duration/Dauer"));
+                }
+                if clip_name.is_none() {
+                    missing.push(column_description(&mapping.clip_name, "Name", "Name/Clip/Track"));
+                }
+                let found = header
+                    .trim_start_matches('\u{feff}')
+                    .split('\t')
+                    .map(|c| format!("'{}'", c.trim()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                Err(Diagnostic::error(
+                    DiagnosticKind::MissingColumn,
+                    format!("Spalte(n) nicht gefunden: {}. Vorhandene Spalten: {}", missing.join(", "), found),
+                )
+                .line(1, header)
+                .suggest("Spaltenzuordnung unter Einstellungen → Tracklisten Import festlegen")
+                .into())
+            }
+        }
+    }
+
+    fn required_len(&self) -> usize {
+        self.duration.max(self.clip_name) + 1
+    }
+}
+
+fn column_description(configured: &str, field: &str, known: &str) -> String {
+    if configured.trim().is_empty() {
+        format!("{} ({})", field, known)
+    } else {
+        format!("{} ('{}' laut Einstellungen)", field, configured.trim())
+    }
+}
+
+/// Tabulatorgetrennte Trackliste mit Kopfzeile. Die Spalten werden über die
+/// Kopfzeile zugeordnet, siehe `TextColumns::from_header`.
+fn parse_text_file(ctx: &mut ParseContext, path: &str) -> Result<()> {
+    let content = read_text_file(ctx, path)?;
+    let mut lines_iter = content.lines();
+    // 1. Zeile (Header): Spaltenzuordnung und Bildraten-Erkennung
+    let header = lines_iter.next().unwrap_or_default();
+    let column_map = TextColumns::from_header(header, &ctx.settings.text_columns)?;
+    if column_map.legacy {
+        ctx.report(
+            Diagnostic::warning(
+                DiagnosticKind::MissingColumn,
+                format!(
+                    "Kopfzeile nicht erkannt, verwende feste Spalten: Dauer = Spalte {}, Name = Spalte {}",
+                    LEGACY_DURATION_COLUMN + 1,
+                    LEGACY_CLIP_NAME_COLUMN + 1
+                ),
+            )
+            .file(path)
+            .line(1, header)
+            .suggest("Spaltenzuordnung unter Einstellungen → Tracklisten Import festlegen"),
+        );
+    }
+    let lines: Vec<&str> = lines_iter.collect();
+    // Zeilennummern in der Datei: Kopfzeile ist Zeile 1
+    let numbered = lines.iter().enumerate().map(|(i, line)| (i + 2, *line));
+
+    let rate = frame_rate_for_file(ctx, path, header, lines.iter().copied());
+
+    for (number, line) in numbered {
         if line.trim().is_empty() {
             continue;
         }
 
         // Zerlegen nach Tabs
         let columns: Vec<&str> = line.split('\t').map(|s| s.trim()).collect();
-        if columns.len() < 3 {
-            let msg = format!("Unerwartetes Zeilenformat (weniger als 3 Spalten): {}", line);
-            app.error_messages.push(msg.clone());
+        if columns.len() < column_map.required_len() {
+            ctx.report(
+                Diagnostic::error(
+                    DiagnosticKind::LineFormat,
+                    format!("Unerwartetes Zeilenformat (weniger als {} Spalten)", column_map.required_len()),
+                )
+                .file(path)
+                .line(number, line)
+                .suggest("Spalten müssen durch Tabulatoren getrennt sein"),
+            );
             continue;
         }
 
-        let duration_str = columns[1];
-        let track_str = columns[2];
+        let duration_str = columns[column_map.duration];
+        let track_str = columns[column_map.clip_name];
 
-        let Some(duration_in_seconds) = parse_hh_mm_ss_frames(duration_str) else {
-            let msg = format!("Konnte Dauer '{}' nicht parsen: {}", duration_str, line);
-            app.error_messages.push(msg.clone());
+        let Some(duration) = Timecode::parse(duration_str, rate) else {
+            ctx.report(
+                Diagnostic::error(
+                    DiagnosticKind::InvalidTimecode,
+                    format!("Konnte Dauer '{}' nicht parsen ({} fps)", duration_str, rate),
+                )
+                .file(path)
+                .line(number, line)
+                .suggest("Dauer als HH:MM:SS:FF angeben oder die Bildrate der Datei prüfen"),
+            );
             continue;
         };
 
-        // Aus dem Dateinamen index/titel/kuenstler holen
-        let (index, titel, kuenstler) = parse_track_filename(track_str);
+        let record_in = column_map
+            .record_in
+            .and_then(|i| columns.get(i))
+            .and_then(|tc| Timecode::parse(tc, rate));
+
+        add_track_event(ctx, path, TrackEvent::new(track_str.to_string(), duration).at(record_in));
+    }
+
+    Ok(())
+}
+
+/// Liest eine Textdatei mit erkannter Kodierung (BOM, UTF-16, UTF-8, Windows-1252).
+/// Zeilen mit nicht dekodierbaren Bytes bleiben erhalten (mit Ersatzzeichen)
+/// und werden einzeln gemeldet.
+pub(crate) fn read_text_file(ctx: &mut ParseContext, path: &str) -> Result<String> {
+    let decoded = textfile::read_text(path)?;
+    info!("{} gelesen als {}", path, decoded.encoding.name());
+
+    let lines: Vec<&str> = decoded.text.lines().collect();
+    for &number in &decoded.invalid_lines {
+        ctx.report(
+            Diagnostic::warning(
+                DiagnosticKind::Encoding,
+                format!("Ungültige Zeichen für {} wurden ersetzt", decoded.encoding.name()),
+            )
+            .file(path)
+            .line(number, lines.get(number - 1).copied().unwrap_or_default())
+            .suggest("Datei als UTF-8 oder UTF-16 neu exportieren"),
+        );
+    }
+
+    Ok(decoded.text)
+}
+
+/// Ein einzelnes Audio-Ereignis aus einer Eingabedatei, unabhängig vom Format
+/// (Textliste, EDL, ...). Alle Parser liefern ihre Ergebnisse in dieser Form.
+pub(crate) struct TrackEvent {
+    pub clip_name: String,
+    pub duration: Timecode,
+    /// Position in der Timeline, sofern das Format sie kennt.
+    pub record_in: Option<Timecode>,
+    /// In der Audiodatei eingebettete Metadaten (BWF, iXML, ID3), sofern vorhanden.
+    pub metadata: Option<AudioMetadata>,
+}
 
-        let label_code = find_label_code(&app.label_dict, &index);
+impl TrackEvent {
+    pub fn new(clip_name: String, duration: Timecode) -> Self {
+        Self {
+            clip_name,
+            duration,
+            record_in: None,
+            metadata: None,
+        }
+    }
 
-        let tracks = app.tracks_per_file.entry(path.to_string()).or_default();
-        if let Some(existing_track) = tracks.iter_mut().find(|t| {
-            t.index == index && t.titel == titel && t.kuenstler == kuenstler
-        }) {
-            existing_track.duration = Some(existing_track.duration.unwrap_or(0.0) + duration_in_seconds);
-            info!("Track aktualisiert (Dauer addiert): {} {} {}", index, titel, kuenstler);
-        } else {
-            // Erst loggen, dann verschieben (um Move-Fehler zu vermeiden)
-            info!("Neuer Track geparst: {} {} {}", index, titel, kuenstler);
+    /// Setzt die Timeline-Position (Record-In) des Ereignisses.
+    pub fn at(mut self, record_in: Option<Timecode>) -> Self {
+        self.record_in = record_in;
+        self
+    }
+}
 
-            tracks.push(TrackInfo {
-                index,
-                titel,
-                kuenstler,
-                duration: Some(duration_in_seconds),
-                label_code,
-            });
+/// Zerlegt den Clipnamen, ermittelt den Labelcode und fasst Ereignisse
+/// desselben Tracks innerhalb einer Datei zusammen.
+/// Eingebettete Metadaten haben Vorrang, der Dateiname füllt nur leere Felder.
+pub(crate) fn add_track_event(ctx: &mut ParseContext, path: &str, event: TrackEvent) {
+    // Aus dem Dateinamen index/titel/kuenstler holen
+    let parsed = parse_track_filename(&ctx.filename_rules, &event.clip_name);
+    // Ohne Regel-Treffer und ohne "_123_"-Block bleibt der ganze Name als Index
+    if parsed.rule.is_empty() && parsed.title.is_empty() && parsed.artist.is_empty() {
+        ctx.report(
+            Diagnostic::warning(
+                DiagnosticKind::ClipName,
+                format!("Kein Index im Clipnamen '{}' erkannt, der ganze Name wird als Index verwendet", event.clip_name),
+            )
+            .file(path)
+            .track(&parsed.index)
+            .suggest("Regel in filename_rules.json ergänzen oder den Clip umbenennen"),
+        );
+    }
+    let (index, mut titel, mut kuenstler, version) = (parsed.index, parsed.title, parsed.artist, parsed.version);
+    let mut label_code = String::new();
+    let mut isrc = String::new();
+    let mut rights_holders = Vec::new();
+
+    if let Some(meta) = &event.metadata {
+        if !meta.composer.is_empty() {
+            rights_holders.push(RightsHolder::new(Role::Composer, meta.composer.clone()));
+        }
+        if !meta.publisher.is_empty() {
+            rights_holders.push(RightsHolder::new(Role::Publisher, meta.publisher.clone()));
+        }
+        if !meta.title.is_empty() {
+            titel = meta.title.to_lowercase();
         }
+        let artist = if meta.artist.is_empty() { &meta.composer } else { &meta.artist };
+        if !artist.is_empty() {
+            kuenstler = artist.to_lowercase();
+        }
+        label_code = meta.label_code.clone();
+        isrc = meta.isrc.clone();
     }
 
-    Ok(())
+    if label_code.is_empty() {
+        label_code = find_label_code(ctx, path, &index);
+    }
+
+    let gap_tolerance = ctx.settings.gap_tolerance_frames(event.duration.rate);
+    let usage = Usage {
+        record_in: event.record_in,
+        duration: event.duration,
+        versions: if version.is_empty() { Vec::new() } else { vec![version.clone()] },
+    };
+
+    let tracks = ctx.tracks_per_file.entry(path.to_string()).or_default();
+    if let Some(existing_track) = tracks.iter_mut().find(|t| {
+        t.index == index && t.titel == titel && t.kuenstler == kuenstler
+    }) {
+        let mut usages = std::mem::take(&mut existing_track.usages);
+        usages.push(usage);
+        existing_track.usages = merge_usages(usages, gap_tolerance);
+        existing_track.duration = total_duration(&existing_track.usages);
+        existing_track.update_span();
+        if existing_track.isrc.is_empty() {
+            existing_track.isrc = isrc;
+        }
+        if existing_track.rights_holders.is_empty() {
+            existing_track.rights_holders = rights_holders;
+        }
+        if !version.is_empty() && !existing_track.versions.contains(&version) {
+            existing_track.versions.push(version);
+        }
+        info!("Track aktualisiert (Verwendung ergänzt): {} {} {}", index, titel, kuenstler);
+    } else {
+        // Erst loggen, dann verschieben (um Move-Fehler zu vermeiden)
+        info!("Neuer Track geparst: {} {} {}", index, titel, kuenstler);
+
+        let occurrence = tracks.iter().filter(|t| t.index == index).count() + 1;
+        let mut track = TrackInfo {
+            key: TrackInfo::row_key(&index, occurrence),
+            index,
+            titel,
+            kuenstler,
+            duration: Some(event.duration),
+            label_code,
+            isrc,
+            usages: vec![usage],
+            versions: if version.is_empty() { Vec::new() } else { vec![version] },
+            first_record_in: None,
+            last_record_out: None,
+            rights_holders,
+            parsed: None,
+        };
+        track.update_span();
+        tracks.push(track);
+    }
+}
+
+/// Fasst die Verwendungen eines Tracks zusammen: überlappende Verwendungen
+/// (z.B. Stereopaar auf A5/A6 oder dieselbe Musik auf zwei Spuren) und solche,
+/// deren Lücke höchstens `gap_tolerance` Frames beträgt, werden zu einer
+/// Verwendung; die überbrückte Lücke zählt mit und die Versionen werden
+/// gemeinsam angegeben. Verwendungen ohne Timeline-Position bleiben einzeln erhalten.
+pub(crate) fn merge_usages(usages: Vec<Usage>, gap_tolerance: i64) -> Vec<Usage> {
+    let Some(rate) = usages.first().map(|u| u.duration.rate) else {
+        return usages;
+    };
+
+    let (mut positioned, unpositioned): (Vec<Usage>, Vec<Usage>) =
+        usages.into_iter().partition(|u| u.record_in.is_some());
+    positioned.sort_by_key(|u| u.record_in.map(|tc| tc.convert(rate).frames));
+
+    let mut merged: Vec<(i64, i64, Vec<String>)> = Vec::new();
+    for usage in positioned {
+        let (Some(tc_in), Some(tc_out)) = (usage.record_in, usage.record_out()) else {
+            continue;
+        };
+        let (start, end) = (tc_in.convert(rate).frames, tc_out.convert(rate).frames);
+        match merged.last_mut() {
+            Some((_, last_end, versions)) if start <= *last_end + gap_tolerance => {
+                *last_end = (*last_end).max(end);
+                for version in usage.versions {
+                    if !versions.contains(&version) {
+                        versions.push(version);
+                    }
+                }
+            }
+            _ => merged.push((start, end, usage.versions)),
+        }
+    }
+
+    merged
+        .into_iter()
+        .map(|(start, end, versions)| Usage {
+            record_in: Some(Timecode::from_frames(start, rate)),
+            duration: Timecode::from_frames(end - start, rate),
+            versions,
+        })
+        .chain(unpositioned)
+        .collect()
+}
+
+/// Hörbare Gesamtdauer der (bereits zusammengefassten) Verwendungen.
+pub(crate) fn total_duration(usages: &[Usage]) -> Option<Timecode> {
+    let rate = usages.first()?.duration.rate;
+    let frames = usages.iter().map(|u| u.duration.convert(rate).frames).sum();
+    Some(Timecode::from_frames(frames, rate))
+}
+
+/// Bestimmt die Bildrate einer Eingabedatei:
+///  1) manuell für diese Datei gewählt
+///  2) fest in den Einstellungen gesetzt
+///  3) im Dateikopf angegeben ("25 fps", "29.97 DF", ...)
+///  4) Drop-Frame-Trenner ';' in den Timecodes => 29,97 DF
+///  5) Rückfallwert aus den Einstellungen
+pub(crate) fn frame_rate_for_file<'a>(
+    ctx: &mut ParseContext,
+    path: &str,
+    header: &str,
+    mut body: impl Iterator<Item = &'a str>,
+) -> FrameRate {
+    let detected = detect_frame_rate(header).or_else(|| {
+        body.any(|l| DROP_FRAME_TC_RE.is_match(l)).then_some(FrameRate::FPS_29_97_DF)
+    });
+    resolve_frame_rate(ctx, path, detected)
+}
+
+/// Wählt die Bildrate für eine Datei, deren Inhalt ggf. schon eine Rate angibt
+/// (z.B. XML-Formate). Manuelle Auswahl und feste Einstellung haben Vorrang.
+pub(crate) fn resolve_frame_rate(ctx: &mut ParseContext, path: &str, detected: Option<FrameRate>) -> FrameRate {
+    if let Some(rate) = detected {
+        ctx.detected_frame_rates.insert(path.to_string(), rate);
+    } else {
+        ctx.detected_frame_rates.remove(path);
+    }
+
+    let rate = ctx
+        .frame_rate_overrides
+        .get(path)
+        .copied()
+        .or(ctx.settings.frame_rate)
+        .or(detected)
+        .unwrap_or(ctx.settings.fallback_frame_rate);
+    info!("Bildrate für '{}': {}", path, rate);
+    rate
 }
 
 /// Parsen von "ANW1832_001_Forgotten-Dreams.wav.new.01"
 ///  1) cut alles hinter .wav/.mp3
 ///  2) cut .wav/.mp3 selbst weg
-///  3) split_index_and_rest => (index_part, rest_part)
-///  4) Letzten Unterstrich vom index weg, falls vorhanden
-///  5) Titel + Künstler aufsplitten
-fn parse_track_filename(filename: &str) -> (String, String, String) {
+///  3) Versionsendungen aus version_suffixes.json abtrennen
+///  4) Regeln aus der Regeldatei (höchste Priorität zuerst); passt keine:
+///  5) split_index_and_rest => (index_part, rest_part)
+///  6) Letzten Unterstrich vom index weg, falls vorhanden
+///  7) Titel + Künstler aufsplitten
+pub(crate) fn parse_track_filename(rules: &FilenameRules, filename: &str) -> RuleMatch {
     // 1) Alles hinter .wav / .mp3 weg
     let base_with_ext = strip_version(filename);
 
@@ -110,26 +531,55 @@ fn parse_track_filename(filename: &str) -> (String, String, String) {
         None => base_with_ext,
     };
 
-    // 3) Bis zu 2 "_123_"-Blöcke rausholen
+    // 3) Versionsendungen (_30sec, _Alt, _Stem_Bass, ...) abtrennen; bleibt danach
+    //    kein Titel übrig, war die Endung Teil des Namens
+    let (base_no_version, version) = rules.versions.strip(base_no_ext);
+    if !version.is_empty() {
+        let mut parsed = split_track_name(rules, base_no_version);
+        if !parsed.title.is_empty() {
+            let version = version.to_lowercase();
+            parsed.version = if parsed.version.is_empty() { version } else { format!("{}_{}", parsed.version, version) };
+            return parsed;
+        }
+    }
+    split_track_name(rules, base_no_ext)
+}
+
+/// Zerlegt einen Dateinamen ohne Endung in Index, Titel und Künstler.
+fn split_track_name(rules: &FilenameRules, base_no_ext: &str) -> RuleMatch {
+    // 4) Regeln aus der Regeldatei
+    if let Some(found) = rules.apply(base_no_ext) {
+        return RuleMatch {
+            index: found.index.to_lowercase(),
+            title: found.title.to_lowercase(),
+            artist: found.artist.to_lowercase(),
+            version: found.version.to_lowercase(),
+            rule: found.rule,
+        };
+    }
+
+    // 5) Bis zu 2 "_123_"-Blöcke rausholen
     let (mut index_part, rest_part) = match split_index_and_rest(base_no_ext) {
         Some(t) => t,
         None => (base_no_ext.to_string(), "".to_string()),
     };
 
-    // 4) Wenn index_part mit '_' endet, abschneiden
+    // 6) Wenn index_part mit '_' endet, abschneiden
     if index_part.ends_with('_') {
         index_part.pop(); // entfernt das letzte Zeichen
     }
 
-    // 5) rest_part in titel + kuenstler zerlegen
+    // 7) rest_part in titel + kuenstler zerlegen
     let (titel, kuenstler) = split_title_and_artist(rest_part);
 
     // in Kleinschreibung
-    (
-        index_part.to_lowercase(),
-        titel.to_lowercase(),
-        kuenstler.to_lowercase(),
-    )
+    RuleMatch {
+        rule: String::new(),
+        index: index_part.to_lowercase(),
+        title: titel.to_lowercase(),
+        artist: kuenstler.to_lowercase(),
+        version: String::new(),
+    }
 }
 
 /// Schneidet alles nach ".wav" oder ".mp3" ab, z.B. ".wav.new.01"
@@ -146,8 +596,7 @@ fn strip_version(filename: &str) -> &str {
 
 /// Regex, um bis zu 2 `_Ziffern_`-Blöcke als "index" zu erkennen
 fn split_index_and_rest(base: &str) -> Option<(String, String)> {
-    let re = Regex::new(r"^(?P<index>.*?_\d+_(?:\d+_)?)(?P<rest>.*)$").unwrap();
-    re.captures(base).map(|caps| {
+    INDEX_RE.captures(base).map(|caps| {
         let index_str = caps["index"].to_string();
         let rest_str  = caps["rest"].to_string();
         (index_str, rest_str)
@@ -169,28 +618,111 @@ fn split_title_and_artist(rest: String) -> (String, String) {
     }
 }
 
-/// Parst die Dauer "00:00:43:12" (HH:MM:SS:Frames, 25 fps als Beispiel)
-fn parse_hh_mm_ss_frames(dur: &str) -> Option<f64> {
-    let parts: Vec<&str> = dur.split(':').collect();
-    if parts.len() != 4 {
-        return None;
+/// Liest label_code basierend auf dem index-Str (z.B. "ANW", "BMGPM", etc.)
+/// Passen mehrere Präfixe mit unterschiedlichen Codes, gewinnt der längste und
+/// der Konflikt landet in den Meldungen.
+pub(crate) fn find_label_code(ctx: &mut ParseContext, path: &str, index_str: &str) -> String {
+    let Some(label_match) = ctx.label_codes.lookup(index_str) else {
+        return String::new();
+    };
+
+    let code = label_match.code.to_string();
+    if !label_match.conflicts.is_empty() {
+        let others = label_match
+            .conflicts
+            .iter()
+            .map(|(prefix, code)| format!("'{}' => {}", prefix, code))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let diagnostic = Diagnostic::warning(
+            DiagnosticKind::LabelCodeConflict,
+            format!(
+                "Labelcode-Konflikt für '{}': '{}' => {} verwendet, ebenfalls passend: {}",
+                index_str, label_match.prefix, label_match.code, others
+            ),
+        )
+        .file(path)
+        .track(index_str)
+        .suggest("Präfixe in labelcodes.json eindeutig machen oder den Labelcode in der Datenbank setzen");
+        ctx.report(diagnostic);
     }
-    let hh = parts[0].parse::<u64>().ok()?;
-    let mm = parts[1].parse::<u64>().ok()?;
-    let ss = parts[2].parse::<u64>().ok()?;
-    let frames = parts[3].parse::<u64>().ok()?;
 
-    let fps = 25.0;
-    let total_seconds = (hh * 3600 + mm * 60 + ss) as f64 + frames as f64 / fps;
-    Some(total_seconds)
+    code
 }
 
-/// Liest label_code basierend auf dem index-Str (z.B. "ANW", "BMGPM", etc.)
-fn find_label_code(label_dict: &std::collections::HashMap<String, String>, index_str: &str) -> String {
-    for (label, code) in label_dict {
-        if index_str.to_uppercase().starts_with(&label.to_uppercase()) {
-            return code.clone();
+#[cfg(test)]
+impl ParseContext {
+    /// Kontext mit Standardeinstellungen, ohne Labelcodes und Dateinamen-Regeln.
+    pub fn for_tests() -> Self {
+        Self {
+            settings: Settings::default(),
+            label_codes: Arc::default(),
+            filename_rules: Arc::default(),
+            frame_rate_overrides: HashMap::new(),
+            tracks_per_file: HashMap::new(),
+            detected_frame_rates: HashMap::new(),
+            diagnostics: Vec::new(),
         }
     }
-    String::new()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audiometa::AudioMetadata;
+    use crate::filename_rules::VersionSuffixes;
+    use crate::timecode::FrameRate;
+
+    fn rules_with_versions() -> FilenameRules {
+        let mut rules = FilenameRules::new(Vec::new());
+        rules.versions = VersionSuffixes::new(["#sec", "#", "Alt"].map(String::from).to_vec());
+        rules
+    }
+
+    fn event_with(metadata: AudioMetadata) -> TrackEvent {
+        let mut event = TrackEvent::new("ANW1832_001_Morning_Light.wav".to_string(), Timecode::from_frames(250, FrameRate::FPS_25));
+        event.metadata = Some(metadata);
+        event
+    }
+
+    #[test]
+    fn composer_and_publisher_from_metadata_become_rights_holders() {
+        let mut ctx = ParseContext::for_tests();
+        let metadata = AudioMetadata {
+            composer: "Jane Doe".to_string(),
+            publisher: "Example Music Publishing".to_string(),
+            ..Default::default()
+        };
+        add_track_event(&mut ctx, "test.wav", event_with(metadata));
+
+        let track = &ctx.tracks_per_file["test.wav"][0];
+        let holders: Vec<_> = track.rights_holders.iter().map(|h| (h.role, h.name.as_str())).collect();
+        assert_eq!(holders, [(Role::Composer, "Jane Doe"), (Role::Publisher, "Example Music Publishing")]);
+    }
+
+    #[test]
+    fn publisher_alone_is_kept() {
+        let mut ctx = ParseContext::for_tests();
+        let metadata = AudioMetadata { publisher: "Example Music Publishing".to_string(), ..Default::default() };
+        add_track_event(&mut ctx, "test.wav", event_with(metadata));
+
+        let track = &ctx.tracks_per_file["test.wav"][0];
+        assert_eq!(track.rights_holders.len(), 1);
+        assert_eq!(track.rights_holders[0].role, Role::Publisher);
+    }
+
+    #[test]
+    fn version_suffix_is_split_off_the_title() {
+        let parsed = parse_track_filename(&rules_with_versions(), "ANW1832_001_Morning_Light_Alt_60.wav");
+        assert_eq!(parsed.index, "anw1832_001");
+        assert_eq!(parsed.title, "morning");
+        assert_eq!(parsed.version, "alt_60");
+    }
+
+    #[test]
+    fn number_suffix_does_not_eat_the_index() {
+        let parsed = parse_track_filename(&rules_with_versions(), "ANW1832_001.wav");
+        assert_eq!(parsed.index, "anw1832_001");
+        assert!(parsed.version.is_empty());
+    }
+}
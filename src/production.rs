@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+/// Angaben zur Produktion bzw. Sendung für den Kopf der Musikfolge.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProductionInfo {
+    pub title: String,
+    pub episode: String,
+    pub production_number: String,
+    pub broadcaster: String,
+    pub broadcast_date: String,
+    /// Sendelänge, z.B. "00:44:30"
+    pub program_length: String,
+    pub company: String,
+}
+
+impl ProductionInfo {
+    /// Beschriftung und Wert jedes Feldes, in der Reihenfolge des Kopfblocks.
+    pub fn fields(&self) -> [(&'static str, &String); 7] {
+        [
+            ("Produktionstitel", &self.title),
+            ("Folge", &self.episode),
+            ("Produktionsnummer", &self.production_number),
+            ("Sender", &self.broadcaster),
+            ("Sendedatum", &self.broadcast_date),
+            ("Sendelänge", &self.program_length),
+            ("Produktionsfirma", &self.company),
+        ]
+    }
+
+    pub fn fields_mut(&mut self) -> [(&'static str, &mut String); 7] {
+        [
+            ("Produktionstitel", &mut self.title),
+            ("Folge", &mut self.episode),
+            ("Produktionsnummer", &mut self.production_number),
+            ("Sender", &mut self.broadcaster),
+            ("Sendedatum", &mut self.broadcast_date),
+            ("Sendelänge", &mut self.program_length),
+            ("Produktionsfirma", &mut self.company),
+        ]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields().iter().all(|(_, value)| value.trim().is_empty())
+    }
+}
+
+/// Produktionsdaten für eine oder mehrere Eingabedateien (z.B. alle Akte einer Folge).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProductionGroup {
+    pub name: String,
+    pub info: ProductionInfo,
+    pub files: Vec<String>,
+}
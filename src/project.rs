@@ -0,0 +1,153 @@
+use crate::cache::{self, CacheEntry, FileFingerprint};
+use crate::model::{TrackInfo, TrackOverride};
+use crate::production::ProductionGroup;
+use crate::timecode::FrameRate;
+use anyhow::{bail, Context, Result};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+pub const PROJECT_EXTENSION: &str = "gemaproj";
+/// Aktuelle Version des Projektformats; ältere Projekte werden weiter gelesen.
+/// Version 2: Zeilenschlüssel für Handänderungen, stabile Hashes (siehe `migrate_v1`).
+pub const PROJECT_VERSION: u32 = 2;
+pub const RECENT_PROJECTS_PATH: &str = "src/assets/recent_projects.json";
+const MAX_RECENT_PROJECTS: usize = 10;
+
+/// Inhalt einer `.gemaproj`-Datei: Eingabedateien mit ihren Ergebnissen,
+/// Handänderungen und Produktionsdaten.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Project {
+    pub version: u32,
+    pub sources: Vec<ProjectSource>,
+    /// Datei -> Zeile (`TrackInfo::key`) -> Änderungen
+    pub track_overrides: HashMap<String, HashMap<String, TrackOverride>>,
+    pub dismissed_candidates: HashSet<String>,
+    pub productions: Vec<ProductionGroup>,
+    pub export_path: Option<String>,
+    /// Name der Dauer-Regel beim Speichern; fehlt in älteren Projekten.
+    pub duration_policy: Option<String>,
+}
+
+/// Eine Eingabedatei in Reihenfolge der Dateiliste.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectSource {
+    pub path: String,
+    pub frame_rate_override: Option<FrameRate>,
+    /// Eingelesener und abgeglichener Stand beim Speichern; fehlt, wenn die Datei
+    /// noch nicht eingelesen war.
+    pub result: Option<CacheEntry>,
+}
+
+/// Stand einer Eingabedatei beim Öffnen im Vergleich zum gespeicherten Ergebnis.
+pub enum SourceState {
+    /// Inhalt wie beim Speichern (ggf. mit neuer Änderungszeit)
+    Unchanged(FileFingerprint),
+    Changed,
+    Missing,
+}
+
+impl Project {
+    pub fn load(path: &str) -> Result<Project> {
+        let file = File::open(path)
+            .with_context(|| format!("Kann Projektdatei '{}' nicht öffnen.", path))?;
+        let mut project: Project = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Projektdatei '{}' ist ungültig.", path))?;
+        if project.version > PROJECT_VERSION {
+            bail!(
+                "Projektdatei '{}' hat Version {}, unterstützt wird bis Version {}. Bitte das Programm aktualisieren.",
+                path,
+                project.version,
+                PROJECT_VERSION
+            );
+        }
+        if project.version < 2 {
+            project.migrate_v1();
+        }
+        info!("Projekt geladen: {} ({} Dateien)", path, project.sources.len());
+        Ok(project)
+    }
+
+    /// Version 1 kannte keine Zeilenschlüssel und bildete die Hashes mit `DefaultHasher`,
+    /// der nicht über Programmversionen stabil ist. Handänderungen waren je Index gespeichert,
+    /// das entspricht dem Schlüssel der ersten Zeile. Stimmt die Änderungszeit einer Datei
+    /// noch, gilt ihr Inhalt als unverändert und der Hash wird neu gebildet.
+    fn migrate_v1(&mut self) {
+        for source in &mut self.sources {
+            let Some(result) = &mut source.result else {
+                continue;
+            };
+            let mut occurrences: HashMap<String, usize> = HashMap::new();
+            for track in &mut result.tracks {
+                let occurrence = occurrences.entry(track.index.clone()).or_default();
+                *occurrence += 1;
+                track.key = TrackInfo::row_key(&track.index, *occurrence);
+            }
+            result.fingerprint = result.fingerprint.and_then(|saved| {
+                let current = cache::fingerprint(&source.path).ok()?;
+                (saved.modified.is_some() && saved.modified == current.modified).then_some(current)
+            });
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        let file = File::create(path)
+            .with_context(|| format!("Kann Projektdatei '{}' nicht schreiben.", path))?;
+        serde_json::to_writer(BufWriter::new(file), self)?;
+        info!("Projekt gespeichert nach {}", path);
+        Ok(())
+    }
+}
+
+/// Vergleicht eine Eingabedatei auf der Platte mit dem gespeicherten Fingerabdruck.
+pub fn source_state(path: &str, saved: Option<FileFingerprint>) -> SourceState {
+    match cache::fingerprint(path) {
+        Ok(current) if saved.is_some_and(|fp| fp.content_hash == current.content_hash) => {
+            SourceState::Unchanged(current)
+        }
+        Ok(_) => SourceState::Changed,
+        Err(_) => SourceState::Missing,
+    }
+}
+
+/// Hängt `.gemaproj` an, falls der Pfad keine Endung hat.
+pub fn with_project_extension(path: &Path) -> String {
+    if path.extension().is_some() {
+        path.to_string_lossy().to_string()
+    } else {
+        path.with_extension(PROJECT_EXTENSION).to_string_lossy().to_string()
+    }
+}
+
+/// Zuletzt geöffnete oder gespeicherte Projekte, neuestes zuerst.
+pub fn load_recent_projects(path: &str) -> Vec<String> {
+    if !Path::new(path).exists() {
+        return Vec::new();
+    }
+    File::open(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| Ok(serde_json::from_reader(BufReader::new(file))?))
+        .unwrap_or_else(|e| {
+            error!("Liste der letzten Projekte '{}' nicht lesbar: {}", path, e);
+            Vec::new()
+        })
+}
+
+pub fn save_recent_projects(path: &str, projects: &[String]) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Kann Liste der letzten Projekte '{}' nicht schreiben.", path))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), projects)?;
+    Ok(())
+}
+
+/// Setzt ein Projekt an den Anfang der Liste und kürzt sie.
+pub fn push_recent_project(projects: &mut Vec<String>, path: &str) {
+    projects.retain(|p| p != path);
+    projects.insert(0, path.to_string());
+    projects.truncate(MAX_RECENT_PROJECTS);
+}
@@ -0,0 +1,298 @@
+use crate::diagnostic::{Diagnostic, DiagnosticKind};
+use crate::parser::{add_track_event, frame_rate_for_file, read_text_file, ParseContext, TrackEvent};
+use crate::textfile::read_text_head;
+use crate::timecode::{FrameRate, Timecode};
+use anyhow::{bail, Result};
+use log::info;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Pro-Tools-Zusätze am Clipnamen: Kanal (".L", ".R", ".1") und Bearbeitungsnummern ("-01").
+static CLIP_SUFFIX_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)(?:\.(?:L|R|C|Ls|Rs|LFE|\d))?(?:-\d{2,})*$").unwrap());
+
+/// Erkennt einen Pro Tools "Session Info as Text"-Export am Kopf ("SESSION NAME:").
+pub fn looks_like_protools(path: &str) -> bool {
+    let Some(head) = read_text_head(path) else {
+        return false;
+    };
+    head.to_uppercase()
+        .lines()
+        .map(|l| l.trim_start_matches('\u{feff}').trim())
+        .find(|l| !l.is_empty())
+        .is_some_and(|l| l.starts_with("SESSION NAME:"))
+}
+
+/// Liest einen Pro Tools Session-Text ein. Übernommen werden nur Events auf
+/// Spuren, deren Name zu den Musikspur-Mustern aus den Einstellungen passt.
+/// Zeiten werden im Format der Session gelesen (Timecode, Min:Sec oder Samples).
+pub fn parse_protools_file(ctx: &mut ParseContext, path: &str) -> Result<()> {
+    let content = read_text_file(ctx, path)?;
+    parse_protools_text(ctx, path, &content)
+}
+
+fn parse_protools_text(ctx: &mut ParseContext, path: &str, content: &str) -> Result<()> {
+    let lines: Vec<&str> = content.lines().collect();
+
+    // Kopfbereich bis zur ersten Sektion ("O N L I N E  F I L E S ...", "T R A C K  L I S T I N G")
+    let header_end = lines
+        .iter()
+        .position(|l| is_section_title(l))
+        .unwrap_or(lines.len());
+    let header: HashMap<String, String> = lines[..header_end]
+        .iter()
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim().to_uppercase(), v.trim().to_string()))
+        .collect();
+
+    let timecode_format = header.get("TIMECODE FORMAT").cloned().unwrap_or_default();
+    let rate = frame_rate_for_file(ctx, path, &timecode_format, std::iter::empty());
+    let sample_rate = header
+        .get("SAMPLE RATE")
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|s| *s > 0.0)
+        .unwrap_or(48000.0) as i64;
+
+    let music_tracks = music_track_patterns(&ctx.settings.protools_music_tracks);
+    if music_tracks.is_empty() {
+        bail!("Keine Musikspuren in den Einstellungen festgelegt (z.B. \"MX*, Musik*\").");
+    }
+
+    let clip_sources = read_clip_list(&lines);
+
+    let mut section = String::new();
+    let mut track_name: Option<String> = None;
+    let mut columns: HashMap<String, usize> = HashMap::new();
+    let mut music_track_found = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        if is_section_title(line) {
+            section = line.split_whitespace().collect::<String>().to_uppercase();
+            track_name = None;
+            continue;
+        }
+        if section != "TRACKLISTING" || line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix("TRACK NAME:") {
+            let name = name.trim().to_string();
+            let is_music = music_tracks.iter().any(|re| re.is_match(&name));
+            info!("Pro Tools Spur '{}' {}", name, if is_music { "(Musik)" } else { "übersprungen" });
+            music_track_found |= is_music;
+            track_name = is_music.then_some(name);
+            columns.clear();
+            continue;
+        }
+
+        let Some(track) = &track_name else {
+            continue;
+        };
+
+        let cells: Vec<&str> = line.split('\t').map(|c| c.trim()).collect();
+        if cells.first().is_some_and(|c| c.eq_ignore_ascii_case("CHANNEL")) {
+            columns = cells
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (column_name(c), i))
+                .collect();
+            continue;
+        }
+        if columns.is_empty() {
+            // Noch Kommentar-/Plug-in-Zeilen vor der Event-Tabelle
+            continue;
+        }
+
+        let cell = |name: &str| columns.get(name).and_then(|&i| cells.get(i)).copied().unwrap_or("");
+        if cell("STATE").eq_ignore_ascii_case("Muted") {
+            continue;
+        }
+
+        let start = parse_session_time(cell("START TIME"), rate, sample_rate);
+        let end = parse_session_time(cell("END TIME"), rate, sample_rate);
+        let duration = parse_session_time(cell("DURATION"), rate, sample_rate)
+            .or_else(|| start.zip(end).map(|(s, e)| e - s));
+
+        let Some(duration) = duration.filter(|d| d.frames > 0) else {
+            ctx.report(
+                Diagnostic::error(
+                    DiagnosticKind::InvalidTimecode,
+                    format!("Pro Tools: Ungültige Zeitangabe auf Spur '{}'", track),
+                )
+                .file(path)
+                .line(i + 1, line),
+            );
+            continue;
+        };
+
+        let clip = cell("CLIP NAME");
+        let clip_name = clip_sources
+            .get(clip)
+            .cloned()
+            .unwrap_or_else(|| strip_clip_suffix(clip));
+
+        add_track_event(ctx, path, TrackEvent::new(clip_name, duration).at(start));
+    }
+
+    if !music_track_found {
+        let msg = format!(
+            "Pro Tools: Keine Spur passt zu den Musikspuren '{}'.",
+            ctx.settings.protools_music_tracks
+        );
+        ctx.report(
+            Diagnostic::warning(DiagnosticKind::NoMusic, msg)
+                .file(path)
+                .suggest("Musikspuren unter Einstellungen → Pro Tools Import anpassen"),
+        );
+    }
+
+    Ok(())
+}
+
+/// Spaltenname der Event-Tabelle; ältere Sessions schreiben "REGION NAME" statt "CLIP NAME".
+fn column_name(cell: &str) -> String {
+    match cell.to_uppercase().as_str() {
+        "REGION NAME" => "CLIP NAME".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// Sektionstitel sind gesperrt geschrieben, z.B. "T R A C K  L I S T I N G"
+/// oder "P L U G - I N S  L I S T I N G".
+fn is_section_title(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() > 6
+        && trimmed.chars().all(|c| c.is_ascii_uppercase() || c == ' ' || c == '-')
+        && trimmed.split(' ').filter(|w| !w.is_empty()).all(|w| w.len() == 1)
+}
+
+/// "O N L I N E  C L I P S  I N  S E S S I O N": Clipname => Quelldatei
+fn read_clip_list(lines: &[&str]) -> HashMap<String, String> {
+    let mut clips = HashMap::new();
+    let mut in_clip_list = false;
+    for line in lines {
+        if is_section_title(line) {
+            let title = line.split_whitespace().collect::<String>();
+            in_clip_list = title.contains("CLIPS") || title.contains("REGIONS");
+            continue;
+        }
+        if !in_clip_list {
+            continue;
+        }
+        let cells: Vec<&str> = line.split('\t').map(|c| c.trim()).collect();
+        if cells.len() < 2 || cells[0].is_empty() || column_name(cells[0]) == "CLIP NAME" {
+            continue;
+        }
+        // Kanal-Suffix der Quelldatei ("[1]", "[2]") entfernen
+        let source = cells[1].split(" [").next().unwrap_or(cells[1]).trim();
+        if !source.is_empty() {
+            clips.insert(cells[0].to_string(), source.to_string());
+        }
+    }
+    clips
+}
+
+/// Entfernt Pro-Tools-Zusätze wie "-01", "-02" (bearbeitete Clips) und ".L"/".R".
+fn strip_clip_suffix(clip: &str) -> String {
+    CLIP_SUFFIX_RE.replace(clip, "").to_string()
+}
+
+/// "MX*, Musik*" => Regexe, Groß-/Kleinschreibung egal, '*' als Platzhalter.
+fn music_track_patterns(text: &str) -> Vec<Regex> {
+    text.split(',')
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .filter_map(|p| {
+            let pattern = regex::escape(p).replace(r"\*", ".*");
+            Regex::new(&format!("(?i)^{}$", pattern)).ok()
+        })
+        .collect()
+}
+
+/// Zeitangabe im Session-Format: Timecode "01:00:00:00", Min:Sec "1:23.456"
+/// oder Samples "1234567".
+fn parse_session_time(text: &str, rate: FrameRate, sample_rate: i64) -> Option<Timecode> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if let Some(tc) = Timecode::parse(text, rate) {
+        return Some(tc);
+    }
+    if let Ok(samples) = text.parse::<i64>() {
+        return Some(Timecode::from_rational_seconds(samples, sample_rate, rate));
+    }
+    // Min:Sec mit Millisekunden
+    let (minutes, seconds) = text.split_once(':')?;
+    let minutes = minutes.parse::<i64>().ok()?;
+    let (secs, millis) = seconds.split_once('.').unwrap_or((seconds, "0"));
+    let millis = format!("{:0<3}", millis);
+    let total_millis = (minutes * 60 + secs.parse::<i64>().ok()?) * 1000 + millis.get(..3)?.parse::<i64>().ok()?;
+    Some(Timecode::from_rational_seconds(total_millis, 1000, rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TrackInfo;
+
+    const SESSION: &str = "SESSION NAME:\tTest
+SAMPLE RATE:\t48000.000000
+BIT DEPTH:\t24-bit
+SESSION START TIMECODE:\t00:59:58:00
+TIMECODE FORMAT:\t25 Frame
+# OF AUDIO TRACKS:\t2
+
+O N L I N E  R E G I O N S  I N  S E S S I O N
+REGION NAME                     \tSOURCE FILE
+MX Cue-01                       \tANW1832_001_Morning_Light.wav [1]
+Dialog-03                       \tDialog.wav [1]
+
+T R A C K  L I S T I N G
+TRACK NAME:\tMX 1
+COMMENTS:\t
+USER DELAY:\t0 Samples
+STATE: \t
+
+CHANNEL \tEVENT   \tREGION NAME                     \tSTART TIME    \tEND TIME      \tDURATION      \tSTATE
+1       \t1       \tMX Cue-01                       \t01:00:10:00   \t01:00:20:00   \t00:00:10:00   \tUnmuted
+1       \t2       \tANW1832_002_Evening_Shade.L-02  \t01:00:30:00   \t01:00:34:00   \t00:00:04:00   \tUnmuted
+1       \t3       \tMX Cue-01                       \t01:01:00:00   \t01:01:05:00   \t00:00:05:00   \tMuted
+
+TRACK NAME:\tDIA 1
+COMMENTS:\t
+USER DELAY:\t0 Samples
+STATE: \t
+
+CHANNEL \tEVENT   \tREGION NAME                     \tSTART TIME    \tEND TIME      \tDURATION      \tSTATE
+1       \t1       \tDialog-03                       \t01:00:00:00   \t01:00:05:00   \t00:00:05:00   \tUnmuted
+";
+
+    fn parse(text: &str) -> Vec<TrackInfo> {
+        let mut ctx = ParseContext::for_tests();
+        parse_protools_text(&mut ctx, "test.txt", text).unwrap();
+        ctx.tracks_per_file.remove("test.txt").unwrap_or_default()
+    }
+
+    #[test]
+    fn region_names_are_resolved_through_the_region_list() {
+        let tracks = parse(SESSION);
+        let track = tracks.iter().find(|t| t.index == "anw1832_001").unwrap();
+        assert_eq!(track.first_record_in.unwrap().frames, 90_250);
+        // die stummgeschaltete Verwendung zählt nicht
+        assert_eq!(track.duration.unwrap().frames, 250);
+    }
+
+    #[test]
+    fn channel_and_edit_suffixes_are_stripped() {
+        let tracks = parse(SESSION);
+        let track = tracks.iter().find(|t| t.index == "anw1832_002").unwrap();
+        assert_eq!(track.duration.unwrap().frames, 100);
+    }
+
+    #[test]
+    fn only_music_tracks_are_read() {
+        assert_eq!(parse(SESSION).len(), 2);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::app::GemaLauncherApp;
+use crate::diagnostic::{Diagnostic, DiagnosticKind, Severity};
+use crate::model::{Role, TrackInfo};
+use crate::settings::{DurationPolicy, ReportMode};
+use crate::timecode::Timecode;
+
+/// Eine Zeile der Ausgabe (Vorschau und CSV): ein ganzer Track (mit erstem In
+/// und letztem Out) oder, im Modus "je Verwendung", eine einzelne Verwendung mit Cue-Nummer.
+pub struct ReportRow<'a> {
+    pub cue: String,
+    pub track: &'a TrackInfo,
+    pub record_in: Option<Timecode>,
+    pub record_out: Option<Timecode>,
+    /// Dauer in Sekunden nach der Dauer-Regel (gerundet, kurze Verwendungen gefiltert).
+    pub duration: Option<f64>,
+    pub version: String,
+}
+
+/// Spaltenüberschriften passend zu `report_cells`. Am Ende folgen die
+/// Beteiligten je Rolle (Komponist, Textdichter, Bearbeiter, Verlag).
+pub fn report_header(mode: ReportMode) -> Vec<&'static str> {
+    let mut header = match mode {
+        ReportMode::Aggregated => vec![
+            "Index", "Titel", "Künstler", "Version", "Erstes In", "Letztes Out", "Dauer", "Labelcode", "ISRC",
+        ],
+        ReportMode::PerUsage => vec![
+            "Cue", "Index", "Titel", "Künstler", "Version", "In", "Out", "Dauer", "Labelcode", "ISRC",
+        ],
+    };
+    header.extend(Role::ALL.iter().map(|role| role.label()));
+    header
+}
+
+/// Zeilen einer Datei. Versionen eines Werks (Stems, Kurzfassungen) stehen
+/// beim Werk in der Spalte "Version". Je Verwendung werden die Einträge nach ihrer
+/// Position in der Timeline sortiert und fortlaufend nummeriert (M1, M2, ...);
+/// Verwendungen ohne Position folgen in Eingabereihenfolge.
+/// Die Dauer-Regel rundet jede Verwendung und lässt kurze Verwendungen weg;
+/// ein Track, von dem nichts übrig bleibt, erscheint nicht.
+pub fn report_rows<'a>(tracks: &'a [TrackInfo], mode: ReportMode, policy: &DurationPolicy) -> Vec<ReportRow<'a>> {
+    match mode {
+        ReportMode::Aggregated => tracks
+            .iter()
+            .filter_map(|track| {
+                let duration = if track.usages.is_empty() {
+                    match track.duration {
+                        Some(duration) => Some(policy.usage_seconds(duration)?),
+                        None => None,
+                    }
+                } else {
+                    let kept: Vec<f64> = track.usages.iter().filter_map(|u| policy.usage_seconds(u.duration)).collect();
+                    if kept.is_empty() {
+                        return None;
+                    }
+                    Some(kept.iter().sum())
+                };
+                Some(ReportRow {
+                    cue: String::new(),
+                    track,
+                    record_in: track.first_record_in,
+                    record_out: track.last_record_out,
+                    duration,
+                    version: track.versions_label(),
+                })
+            })
+            .collect(),
+        ReportMode::PerUsage => {
+            let mut usages: Vec<_> = tracks
+                .iter()
+                .flat_map(|track| track.usages.iter().map(move |usage| (track, usage)))
+                .filter_map(|(track, usage)| Some((track, usage, policy.usage_seconds(usage.duration)?)))
+                .collect();
+            usages.sort_by_key(|(_, usage, _)| match usage.record_in {
+                Some(tc) => (0, tc.frames),
+                None => (1, 0),
+            });
+
+            usages
+                .into_iter()
+                .enumerate()
+                .map(|(i, (track, usage, seconds))| ReportRow {
+                    cue: format!("M{}", i + 1),
+                    track,
+                    record_in: usage.record_in,
+                    record_out: usage.record_out(),
+                    duration: Some(seconds),
+                    version: usage.versions.join(", "),
+                })
+                .collect()
+        }
+    }
+}
+
+/// Meldungen für Tracks, die die Dauer-Regel ganz aus der Ausgabe nimmt.
+pub fn policy_diagnostics(tracks: &[TrackInfo], policy: &DurationPolicy, file: &str) -> Vec<Diagnostic> {
+    tracks
+        .iter()
+        .filter(|track| {
+            if track.usages.is_empty() {
+                track.duration.is_some_and(|d| policy.usage_seconds(d).is_none())
+            } else {
+                track.usages.iter().all(|u| policy.usage_seconds(u.duration).is_none())
+            }
+        })
+        .map(|track| {
+            Diagnostic::new(
+                Severity::Info,
+                DiagnosticKind::Export,
+                format!(
+                    "'{}' nicht exportiert: alle Verwendungen kürzer als {} s (Dauer-Regel '{}')",
+                    track.index, policy.min_usage_seconds, policy.name
+                ),
+            )
+            .file(file)
+            .track(&track.index)
+        })
+        .collect()
+}
+
+/// Summe der gemeldeten Dauern, für die Summenzeile.
+pub fn report_total(rows: &[ReportRow]) -> f64 {
+    rows.iter().filter_map(|row| row.duration).sum()
+}
+
+impl GemaLauncherApp {
+    /// Formatierte Zellen einer Zeile. Mit `excel_text` wird die Dauer mit "' "
+    /// eingeleitet, damit Excel sie nicht als Uhrzeit umdeutet.
+    pub fn report_cells(&self, row: &ReportRow, mode: ReportMode, excel_text: bool) -> Vec<String> {
+        let duration = row.duration.map_or(String::new(), |d| self.format_duration(d));
+        let duration = if excel_text { format!("' {}", duration) } else { duration };
+        let timecode = |tc: Option<Timecode>| tc.map_or(String::new(), |tc| tc.to_string());
+
+        let track = row.track;
+        let mut cells = match mode {
+            ReportMode::Aggregated => vec![
+                track.index.clone(),
+                track.titel.clone(),
+                track.kuenstler.clone(),
+                row.version.clone(),
+                timecode(row.record_in),
+                timecode(row.record_out),
+                duration,
+                track.label_code.clone(),
+                track.isrc.clone(),
+            ],
+            ReportMode::PerUsage => vec![
+                row.cue.clone(),
+                track.index.clone(),
+                track.titel.clone(),
+                track.kuenstler.clone(),
+                row.version.clone(),
+                timecode(row.record_in),
+                timecode(row.record_out),
+                duration,
+                track.label_code.clone(),
+                track.isrc.clone(),
+            ],
+        };
+        cells.extend(Role::ALL.iter().map(|role| track.rights_label(*role)));
+        cells
+    }
+
+    /// Summenzeile: "Gesamt" in der ersten Spalte, die Summe in der Spalte "Dauer".
+    pub fn report_total_cells(&self, total: f64, mode: ReportMode, excel_text: bool) -> Vec<String> {
+        let header = report_header(mode);
+        let mut cells = vec![String::new(); header.len()];
+        cells[0] = "Gesamt".to_string();
+        if let Some(column) = header.iter().position(|c| *c == "Dauer") {
+            let duration = self.format_duration(total);
+            cells[column] = if excel_text { format!("' {}", duration) } else { duration };
+        }
+        cells
+    }
+}
@@ -0,0 +1,332 @@
+use crate::cache::StableHasher;
+use crate::timecode::{FrameRate, Timecode};
+use anyhow::{Context, Result};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+pub const SETTINGS_PATH: &str = "src/assets/settings.json";
+
+/// Dauerhafte Einstellungen, die zwischen zwei Programmstarts erhalten bleiben.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Feste Bildrate für alle Dateien; `None` = aus dem Dateikopf erkennen.
+    pub frame_rate: Option<FrameRate>,
+    /// Bildrate, falls im Dateikopf nichts gefunden wird.
+    pub fallback_frame_rate: FrameRate,
+    /// Gespeicherte CSV-Exportprofile und der Name des aktiven Profils.
+    pub export_profiles: Vec<ExportProfile>,
+    pub active_export_profile: String,
+    /// Audiokanäle, die aus EDLs übernommen werden ("1-4, 7"); leer = alle.
+    pub edl_audio_channels: String,
+    /// Pro Tools Spuren, die als Musik gelten ("MX*, Musik*"), '*' = Platzhalter.
+    pub protools_music_tracks: String,
+    /// Ein Eintrag je Track oder je Verwendung (Cue-Sheet mit M1, M2, ...).
+    pub report_mode: ReportMode,
+    /// Verwendungen desselben Tracks mit höchstens dieser Lücke (Sekunden) werden zusammengefasst.
+    pub gap_tolerance_seconds: f64,
+    /// Eigene Spaltenzuordnung für Tracklisten (.txt) mit unbekanntem Kopf.
+    pub text_columns: TextColumnMapping,
+    /// Unscharfe Datenbank-Treffer ab dieser Konfidenz (0–1) automatisch übernehmen,
+    /// darunter nur als Vorschlag anzeigen.
+    pub fuzzy_match_threshold: f64,
+    /// Gespeicherte Dauer-Regeln (Rundung, Mindestdauer, Summe) und die aktive Regel.
+    pub duration_policies: Vec<DurationPolicy>,
+    pub active_duration_policy: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            frame_rate: None,
+            fallback_frame_rate: FrameRate::FPS_25,
+            export_profiles: ExportProfile::defaults(),
+            active_export_profile: "Standard".to_string(),
+            edl_audio_channels: String::new(),
+            protools_music_tracks: "MX*, Musik*, Music*".to_string(),
+            report_mode: ReportMode::Aggregated,
+            gap_tolerance_seconds: 0.0,
+            text_columns: TextColumnMapping::default(),
+            fuzzy_match_threshold: 0.9,
+            duration_policies: DurationPolicy::defaults(),
+            active_duration_policy: "Genau".to_string(),
+        }
+    }
+}
+
+impl Settings {
+    /// Aktives Exportprofil; fällt auf das erste Profil bzw. den Standard zurück.
+    pub fn export_profile(&self) -> ExportProfile {
+        self.export_profiles
+            .iter()
+            .find(|p| p.name == self.active_export_profile)
+            .or_else(|| self.export_profiles.first())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Aktive Dauer-Regel; fällt auf die erste Regel bzw. den Standard zurück.
+    pub fn duration_policy(&self) -> DurationPolicy {
+        self.duration_policies
+            .iter()
+            .find(|p| p.name == self.active_duration_policy)
+            .or_else(|| self.duration_policies.first())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Hash der Einstellungen, die Einlesen und Datenbank-Abgleich bestimmen. Export,
+    /// Ausgabeform und Dauer-Regeln fehlen, damit sie keinen neuen Durchlauf auslösen.
+    pub fn parse_hash(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+        hasher.write_str(&self.frame_rate.map(|rate| rate.to_string()).unwrap_or_default());
+        hasher.write_str(&self.fallback_frame_rate.to_string());
+        hasher.write_str(&self.edl_audio_channels);
+        hasher.write_str(&self.protools_music_tracks);
+        hasher.write(&self.gap_tolerance_seconds.to_bits().to_le_bytes());
+        hasher.write_str(&self.text_columns.duration);
+        hasher.write_str(&self.text_columns.clip_name);
+        hasher.write_str(&self.text_columns.record_in);
+        hasher.write(&self.fuzzy_match_threshold.to_bits().to_le_bytes());
+        hasher.finish()
+    }
+
+    /// Lückentoleranz in ganzen Frames der angegebenen Bildrate.
+    pub fn gap_tolerance_frames(&self, rate: FrameRate) -> i64 {
+        (self.gap_tolerance_seconds.max(0.0) * rate.num as f64 / rate.den as f64).round() as i64
+    }
+}
+
+/// Spaltennamen (oder 1-basierte Spaltennummern) einer Trackliste.
+/// Leer = anhand bekannter Kopfzeilen erkennen ("Dauer", "Name", ...).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextColumnMapping {
+    pub duration: String,
+    pub clip_name: String,
+    pub record_in: String,
+}
+
+/// Ausgabe zusammengefasst (eine Zeile je Track) oder je Verwendung.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReportMode {
+    Aggregated,
+    PerUsage,
+}
+
+impl ReportMode {
+    pub const ALL: [ReportMode; 2] = [ReportMode::Aggregated, ReportMode::PerUsage];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReportMode::Aggregated => "Zusammengefasst (je Track)",
+            ReportMode::PerUsage => "Je Verwendung (Cue-Sheet)",
+        }
+    }
+}
+
+/// Rundung der Dauer je Verwendung, bevor summiert wird.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DurationRounding {
+    /// Sekunden und Hundertstel wie eingelesen
+    Exact,
+    UpToSecond,
+    NearestSecond,
+}
+
+impl DurationRounding {
+    pub const ALL: [DurationRounding; 3] =
+        [DurationRounding::Exact, DurationRounding::UpToSecond, DurationRounding::NearestSecond];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DurationRounding::Exact => "Genau (Hundertstel)",
+            DurationRounding::UpToSecond => "Auf ganze Sekunden aufrunden",
+            DurationRounding::NearestSecond => "Auf ganze Sekunden runden",
+        }
+    }
+}
+
+/// Umgang mit Verwendungen unter der Mindestdauer (kurze Blenden, Fragmente).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShortUsage {
+    Count,
+    Drop,
+}
+
+impl ShortUsage {
+    pub const ALL: [ShortUsage; 2] = [ShortUsage::Count, ShortUsage::Drop];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ShortUsage::Count => "Trotzdem melden",
+            ShortUsage::Drop => "Weglassen",
+        }
+    }
+}
+
+/// Benannte Regel, wie Dauern für Vorschau und Export gerundet, gefiltert
+/// und summiert werden, z.B. "Sender" mit Aufrunden je Verwendung.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DurationPolicy {
+    pub name: String,
+    pub rounding: DurationRounding,
+    /// Verwendungen unter dieser Dauer (Sekunden, vor dem Runden) gelten als kurz; 0 = aus.
+    pub min_usage_seconds: f64,
+    pub short_usage: ShortUsage,
+    /// Summenzeile am Ende der Ausgabe.
+    pub total_line: bool,
+}
+
+impl Default for DurationPolicy {
+    fn default() -> Self {
+        Self {
+            name: "Genau".to_string(),
+            rounding: DurationRounding::Exact,
+            min_usage_seconds: 0.0,
+            short_usage: ShortUsage::Count,
+            total_line: false,
+        }
+    }
+}
+
+impl DurationPolicy {
+    pub fn defaults() -> Vec<DurationPolicy> {
+        vec![
+            DurationPolicy::default(),
+            DurationPolicy {
+                name: "Sender".to_string(),
+                rounding: DurationRounding::UpToSecond,
+                min_usage_seconds: 1.0,
+                short_usage: ShortUsage::Drop,
+                total_line: true,
+            },
+        ]
+    }
+
+    /// Gemeldete Dauer einer Verwendung in Sekunden; `None` = wird weggelassen.
+    pub fn usage_seconds(&self, duration: Timecode) -> Option<f64> {
+        if self.short_usage == ShortUsage::Drop && duration.to_seconds() < self.min_usage_seconds {
+            return None;
+        }
+        Some(match self.rounding {
+            DurationRounding::Exact => duration.to_seconds(),
+            DurationRounding::UpToSecond => duration.seconds_ceil() as f64,
+            DurationRounding::NearestSecond => duration.seconds_round() as f64,
+        })
+    }
+}
+
+/// Zeichenkodierung der exportierten CSV-Dateien.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CsvEncoding {
+    Utf8Bom,
+    Utf8,
+    Windows1252,
+}
+
+impl CsvEncoding {
+    pub const ALL: [CsvEncoding; 3] = [CsvEncoding::Utf8Bom, CsvEncoding::Utf8, CsvEncoding::Windows1252];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CsvEncoding::Utf8Bom => "UTF-8 mit BOM",
+            CsvEncoding::Utf8 => "UTF-8",
+            CsvEncoding::Windows1252 => "Windows-1252",
+        }
+    }
+}
+
+/// Wann Felder in Anführungszeichen gesetzt werden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CsvQuoting {
+    /// Nur wenn nötig (Trennzeichen, Anführungszeichen, Zeilenumbruch), nach RFC 4180.
+    Necessary,
+    Always,
+}
+
+impl CsvQuoting {
+    pub const ALL: [CsvQuoting; 2] = [CsvQuoting::Necessary, CsvQuoting::Always];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CsvQuoting::Necessary => "Nur wenn nötig",
+            CsvQuoting::Always => "Immer",
+        }
+    }
+}
+
+/// Benanntes Set an CSV-Optionen, z.B. "Excel (Deutsch)" mit ';' und BOM.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportProfile {
+    pub name: String,
+    pub delimiter: char,
+    pub encoding: CsvEncoding,
+    pub quoting: CsvQuoting,
+    /// Dauer mit "' " einleiten, damit Excel sie nicht als Uhrzeit umdeutet.
+    pub excel_text_duration: bool,
+}
+
+impl Default for ExportProfile {
+    fn default() -> Self {
+        Self {
+            name: "Standard".to_string(),
+            delimiter: ',',
+            encoding: CsvEncoding::Utf8,
+            quoting: CsvQuoting::Necessary,
+            excel_text_duration: true,
+        }
+    }
+}
+
+impl ExportProfile {
+    pub fn defaults() -> Vec<ExportProfile> {
+        vec![
+            ExportProfile {
+                name: "Excel (Deutsch)".to_string(),
+                delimiter: ';',
+                encoding: CsvEncoding::Utf8Bom,
+                ..Default::default()
+            },
+            ExportProfile::default(),
+        ]
+    }
+}
+
+pub fn load_settings(path: &str) -> Result<Settings> {
+    if !Path::new(path).exists() {
+        info!("Einstellungsdatei '{}' nicht gefunden, verwende Standardwerte.", path);
+        return Ok(Settings::default());
+    }
+
+    let file = File::open(path)
+        .with_context(|| format!("Kann Einstellungsdatei '{}' nicht öffnen.", path))?;
+    let reader = BufReader::new(file);
+
+    let settings = match serde_json::from_reader(reader) {
+        Ok(settings) => {
+            info!("Einstellungen erfolgreich geladen.");
+            settings
+        }
+        Err(e) => {
+            error!("Fehler beim Parsen der Einstellungsdatei: {}", e);
+            Settings::default()
+        }
+    };
+
+    Ok(settings)
+}
+
+pub fn save_settings(path: &str, settings: &Settings) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Kann Einstellungsdatei '{}' nicht schreiben.", path))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), settings)?;
+    info!("Einstellungen gespeichert nach {}", path);
+    Ok(())
+}
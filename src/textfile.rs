@@ -0,0 +1,85 @@
+use anyhow::Result;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use std::fs::{self, File};
+use std::io::Read;
+
+/// So viel vom Dateianfang reicht, um ein Format oder eine Kodierung zu erkennen.
+const HEAD_LEN: u64 = 4096;
+
+/// Inhalt einer Textdatei nach dem Dekodieren.
+pub struct DecodedText {
+    pub text: String,
+    pub encoding: &'static Encoding,
+    /// Zeilen (1-basiert), in denen ungültige Bytes ersetzt werden mussten.
+    pub invalid_lines: Vec<usize>,
+}
+
+pub fn read_text(path: &str) -> Result<DecodedText> {
+    Ok(decode_text(&fs::read(path)?))
+}
+
+/// Dekodiert nur den Anfang einer Datei, damit die Formaterkennung große Dateien nicht ganz liest.
+pub fn read_text_head(path: &str) -> Option<String> {
+    let mut head = Vec::new();
+    File::open(path).ok()?.take(HEAD_LEN).read_to_end(&mut head).ok()?;
+    Some(decode_text(&head).text)
+}
+
+/// Dekodiert Textdateien aus Schnitt- und Tonsystemen. Ein BOM (UTF-8,
+/// UTF-16 LE/BE) hat Vorrang, sonst entscheidet `guess_encoding`.
+pub fn decode_text(bytes: &[u8]) -> DecodedText {
+    let (encoding, bom_len) = Encoding::for_bom(bytes).unwrap_or_else(|| (guess_encoding(bytes), 0));
+    let (text, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+
+    let invalid_lines = if had_errors {
+        text.lines()
+            .enumerate()
+            .filter(|(_, line)| line.contains('\u{FFFD}'))
+            .map(|(i, _)| i + 1)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    DecodedText {
+        text: text.into_owned(),
+        encoding,
+        invalid_lines,
+    }
+}
+
+/// Ohne BOM: UTF-16 erkennt man an den Null-Bytes jedes zweiten Zeichens.
+/// Sonst UTF-8, solange die Mehrheit der Zeilen mit Umlauten gültiges UTF-8 ist
+/// (einzelne kaputte Zeilen werden dann gemeldet); ansonsten Windows-1252,
+/// das ältere Avid- und Pro-Tools-Versionen unter Windows schreiben.
+fn guess_encoding(bytes: &[u8]) -> &'static Encoding {
+    let sample = &bytes[..bytes.len().min(4096) & !1];
+    let pairs = sample.len() / 2;
+    if pairs >= 2 {
+        let zeros_even = sample.iter().step_by(2).filter(|b| **b == 0).count();
+        let zeros_odd = sample.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+        if zeros_odd * 10 > pairs * 3 && zeros_even * 10 < pairs {
+            return UTF_16LE;
+        }
+        if zeros_even * 10 > pairs * 3 && zeros_odd * 10 < pairs {
+            return UTF_16BE;
+        }
+    }
+
+    let (mut valid_non_ascii, mut invalid) = (0usize, 0usize);
+    for line in bytes.split(|b| *b == b'\n') {
+        if line.is_ascii() {
+            continue;
+        }
+        match std::str::from_utf8(line) {
+            Ok(_) => valid_non_ascii += 1,
+            Err(_) => invalid += 1,
+        }
+    }
+
+    if invalid == 0 || valid_non_ascii > invalid {
+        UTF_8
+    } else {
+        WINDOWS_1252
+    }
+}
@@ -0,0 +1,322 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Add, Sub};
+use std::sync::LazyLock;
+
+static FRAME_RATE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(\d{2}(?:[.,]\d{1,3})?)\s*(?:fps|frames?|bilder)?\s*(ndf|df|non[ -]?drop(?:[ -]?frame)?|drop[ -]?frame)?\b",
+    )
+    .unwrap()
+});
+
+/// Bildrate als exakter Bruch (z.B. 30000/1001 für 29,97 fps).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FrameRate {
+    pub num: u32,
+    pub den: u32,
+    pub drop_frame: bool,
+}
+
+impl FrameRate {
+    pub const FPS_23_976: FrameRate = FrameRate::new(24000, 1001, false);
+    pub const FPS_24: FrameRate = FrameRate::new(24, 1, false);
+    pub const FPS_25: FrameRate = FrameRate::new(25, 1, false);
+    pub const FPS_29_97: FrameRate = FrameRate::new(30000, 1001, false);
+    pub const FPS_29_97_DF: FrameRate = FrameRate::new(30000, 1001, true);
+    pub const FPS_30: FrameRate = FrameRate::new(30, 1, false);
+    pub const FPS_50: FrameRate = FrameRate::new(50, 1, false);
+    pub const FPS_59_94: FrameRate = FrameRate::new(60000, 1001, false);
+    pub const FPS_59_94_DF: FrameRate = FrameRate::new(60000, 1001, true);
+    pub const FPS_60: FrameRate = FrameRate::new(60, 1, false);
+
+    /// Alle unterstützten Bildraten in der Reihenfolge, in der sie in der UI auftauchen.
+    pub const ALL: [FrameRate; 10] = [
+        FrameRate::FPS_23_976,
+        FrameRate::FPS_24,
+        FrameRate::FPS_25,
+        FrameRate::FPS_29_97,
+        FrameRate::FPS_29_97_DF,
+        FrameRate::FPS_30,
+        FrameRate::FPS_50,
+        FrameRate::FPS_59_94,
+        FrameRate::FPS_59_94_DF,
+        FrameRate::FPS_60,
+    ];
+
+    pub const fn new(num: u32, den: u32, drop_frame: bool) -> Self {
+        Self { num, den, drop_frame }
+    }
+
+    /// Ganzzahlige Frames pro Timecode-Sekunde (29,97 => 30).
+    pub fn nominal(&self) -> u32 {
+        (self.num + self.den / 2) / self.den
+    }
+
+    /// Drop-Frame ist nur für 29,97 und 59,94 definiert.
+    pub fn supports_drop_frame(&self) -> bool {
+        self.den == 1001 && (self.nominal() == 30 || self.nominal() == 60)
+    }
+
+    /// Anzahl der pro Minute (außer jeder zehnten) übersprungenen Frame-Nummern.
+    fn dropped_per_minute(&self) -> i64 {
+        if self.drop_frame {
+            (self.nominal() / 15) as i64
+        } else {
+            0
+        }
+    }
+
+    pub fn label(&self) -> String {
+        let base = match (self.num, self.den) {
+            (24000, 1001) => "23.976".to_string(),
+            (30000, 1001) => "29.97".to_string(),
+            (60000, 1001) => "59.94".to_string(),
+            (n, 1) => n.to_string(),
+            (n, d) => format!("{:.3}", n as f64 / d as f64),
+        };
+        if self.drop_frame {
+            format!("{} DF", base)
+        } else {
+            base
+        }
+    }
+
+    /// Liest Angaben wie "25", "29,97 DF", "23.98 fps" oder "59.94 NDF".
+    pub fn from_label(label: &str) -> Option<Self> {
+        let lower = label.trim().to_lowercase();
+        let number: String = lower
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.' || *c == ',')
+            .collect();
+        let rest = lower[number.len()..].trim();
+        let value: f64 = number.replace(',', ".").parse().ok()?;
+
+        let drop_frame = (rest.contains("df") && !rest.contains("ndf"))
+            || (rest.contains("drop") && !rest.contains("non"));
+
+        let base = FrameRate::ALL
+            .iter()
+            .filter(|r| !r.drop_frame)
+            .find(|r| (r.num as f64 / r.den as f64 - value).abs() < 0.01)
+            .copied()?;
+
+        Some(base.with_drop_frame(drop_frame))
+    }
+
+    /// Liefert dieselbe Rate mit/ohne Drop-Frame, sofern die Rate Drop-Frame kennt.
+    pub fn with_drop_frame(self, drop_frame: bool) -> Self {
+        Self {
+            drop_frame: drop_frame && self.supports_drop_frame(),
+            ..self
+        }
+    }
+}
+
+impl Default for FrameRate {
+    fn default() -> Self {
+        FrameRate::FPS_25
+    }
+}
+
+impl fmt::Display for FrameRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+/// Sucht im Kopf einer Datei nach einer Bildraten-Angabe ("25 fps", "29.97 DF", ...).
+pub fn detect_frame_rate(text: &str) -> Option<FrameRate> {
+    for caps in FRAME_RATE_RE.captures_iter(text) {
+        // Ohne "fps" oder DF/NDF-Angabe ist eine Zahl zu unsicher (z.B. Teil eines Timecodes).
+        let whole = caps.get(0).map_or("", |m| m.as_str()).to_lowercase();
+        let has_unit = whole.contains("fps") || whole.contains("frame") || whole.contains("bilder");
+        if !has_unit && caps.get(2).is_none() {
+            continue;
+        }
+        let label = format!("{} {}", &caps[1], caps.get(2).map_or("", |m| m.as_str()));
+        if let Some(rate) = FrameRate::from_label(&label) {
+            return Some(rate);
+        }
+    }
+    None
+}
+
+/// Timecode bzw. Dauer als ganzzahlige Frame-Anzahl bei einer festen Bildrate.
+/// Gerechnet wird ausschließlich in Frames, Sekunden entstehen erst bei der Ausgabe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timecode {
+    pub frames: i64,
+    pub rate: FrameRate,
+}
+
+impl Timecode {
+    pub fn from_frames(frames: i64, rate: FrameRate) -> Self {
+        Self { frames, rate }
+    }
+
+    /// Wandelt eine Zeitangabe als Bruch (Sekunden = num/den) in Frames um,
+    /// gerundet auf den nächsten ganzen Frame.
+    pub fn from_rational_seconds(num: i64, den: i64, rate: FrameRate) -> Self {
+        let numerator = num as i128 * rate.num as i128;
+        let denominator = den as i128 * rate.den as i128;
+        let frames = (2 * numerator + denominator).div_euclid(2 * denominator);
+        Self { frames: frames as i64, rate }
+    }
+
+    /// Parst "HH:MM:SS:FF" bzw. "HH:MM:SS;FF" (Drop-Frame).
+    /// Ein ';' oder ',' schaltet bei 29,97/59,94 auf Drop-Frame um, '.' ist Non-Drop.
+    pub fn parse(text: &str, rate: FrameRate) -> Option<Self> {
+        let text = text.trim();
+        let parts: Vec<&str> = text.split([':', ';', '.', ',']).collect();
+        if parts.len() != 4 {
+            return None;
+        }
+        let hh = parts[0].parse::<i64>().ok()?;
+        let mm = parts[1].parse::<i64>().ok()?;
+        let ss = parts[2].parse::<i64>().ok()?;
+        let ff = parts[3].parse::<i64>().ok()?;
+
+        let df_separator = text.contains([';', ',']);
+        let rate = if df_separator { rate.with_drop_frame(true) } else { rate };
+
+        let nominal = rate.nominal() as i64;
+        if mm >= 60 || ss >= 60 || ff >= nominal {
+            return None;
+        }
+
+        let drop = rate.dropped_per_minute();
+        if drop > 0 && ss == 0 && mm % 10 != 0 && ff < drop {
+            // Diese Frame-Nummern existieren im Drop-Frame-Timecode nicht.
+            return None;
+        }
+
+        let total_minutes = hh * 60 + mm;
+        let frames = ((hh * 3600 + mm * 60 + ss) * nominal + ff)
+            - drop * (total_minutes - total_minutes / 10);
+        Some(Self { frames, rate })
+    }
+
+    /// Exakte Umrechnung über den Bruch der Bildrate.
+    pub fn to_seconds(self) -> f64 {
+        (self.frames as f64 * self.rate.den as f64) / self.rate.num as f64
+    }
+
+    /// Ganze Sekunden, aufgerundet (über den exakten Bruch, ohne Gleitkommafehler).
+    pub fn seconds_ceil(self) -> i64 {
+        let numerator = self.frames as i128 * self.rate.den as i128;
+        let denominator = self.rate.num as i128;
+        (numerator + denominator - 1).div_euclid(denominator) as i64
+    }
+
+    /// Ganze Sekunden, kaufmännisch gerundet.
+    pub fn seconds_round(self) -> i64 {
+        let numerator = self.frames as i128 * self.rate.den as i128;
+        let denominator = self.rate.num as i128;
+        (2 * numerator + denominator).div_euclid(2 * denominator) as i64
+    }
+
+    /// Rechnet auf eine andere Bildrate um (auf ganze Frames gerundet).
+    pub fn convert(&self, rate: FrameRate) -> Self {
+        if rate.num as u64 * self.rate.den as u64 == self.rate.num as u64 * rate.den as u64 {
+            return Self { frames: self.frames, rate };
+        }
+        let numerator = self.frames as i128 * self.rate.den as i128 * rate.num as i128;
+        let denominator = self.rate.num as i128 * rate.den as i128;
+        let frames = (2 * numerator + denominator).div_euclid(2 * denominator);
+        Self { frames: frames as i64, rate }
+    }
+}
+
+impl Add for Timecode {
+    type Output = Timecode;
+
+    fn add(self, other: Timecode) -> Timecode {
+        Timecode::from_frames(self.frames + other.convert(self.rate).frames, self.rate)
+    }
+}
+
+impl Sub for Timecode {
+    type Output = Timecode;
+
+    fn sub(self, other: Timecode) -> Timecode {
+        Timecode::from_frames(self.frames - other.convert(self.rate).frames, self.rate)
+    }
+}
+
+impl fmt::Display for Timecode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nominal = self.rate.nominal() as i64;
+        let drop = self.rate.dropped_per_minute();
+        let sign = if self.frames < 0 { "-" } else { "" };
+        let mut frames = self.frames.abs();
+
+        if drop > 0 {
+            // Übersprungene Frame-Nummern wieder einrechnen, um das Label zu erhalten.
+            let per_ten_minutes = nominal * 600 - drop * 9;
+            let per_minute = nominal * 60 - drop;
+            let tens = frames / per_ten_minutes;
+            let rem = frames % per_ten_minutes;
+            let extra = if rem > drop { drop * ((rem - drop) / per_minute) } else { 0 };
+            frames += drop * 9 * tens + extra;
+        }
+
+        let ff = frames % nominal;
+        let total_seconds = frames / nominal;
+        let separator = if self.rate.drop_frame { ';' } else { ':' };
+        write!(
+            f,
+            "{}{:02}:{:02}:{:02}{}{:02}",
+            sign,
+            total_seconds / 3600,
+            (total_seconds / 60) % 60,
+            total_seconds % 60,
+            separator,
+            ff
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn df(text: &str) -> Option<i64> {
+        Timecode::parse(text, FrameRate::FPS_29_97_DF).map(|tc| tc.frames)
+    }
+
+    #[test]
+    fn drop_frame_skips_two_frames_at_each_minute() {
+        assert_eq!(df("00:00:59;29"), Some(1799));
+        assert_eq!(df("00:01:00;02"), Some(1800));
+        assert_eq!(df("00:01:00;00"), None);
+        assert_eq!(df("00:01:00;01"), None);
+        assert_eq!(Timecode::from_frames(1800, FrameRate::FPS_29_97_DF).to_string(), "00:01:00;02");
+    }
+
+    #[test]
+    fn drop_frame_keeps_every_tenth_minute() {
+        assert_eq!(df("00:09:59;29"), Some(17981));
+        assert_eq!(df("00:10:00;00"), Some(17982));
+        assert_eq!(Timecode::from_frames(17982, FrameRate::FPS_29_97_DF).to_string(), "00:10:00;00");
+        assert_eq!(df("01:00:00;00"), Some(107892));
+    }
+
+    #[test]
+    fn drop_frame_round_trip() {
+        for frames in (0..200_000).step_by(7) {
+            let label = Timecode::from_frames(frames, FrameRate::FPS_29_97_DF).to_string();
+            assert_eq!(df(&label), Some(frames), "{}", label);
+        }
+    }
+
+    #[test]
+    fn separator_selects_drop_frame() {
+        let rate = FrameRate::FPS_29_97;
+        assert!(Timecode::parse("00:01:00;02", rate).unwrap().rate.drop_frame);
+        assert!(Timecode::parse("00:01:00,02", rate).unwrap().rate.drop_frame);
+        assert!(!Timecode::parse("00:01:00.02", rate).unwrap().rate.drop_frame);
+        assert!(!Timecode::parse("00:01:00:02", rate).unwrap().rate.drop_frame);
+    }
+}
@@ -1,42 +1,109 @@
 use eframe::egui;
+use std::path::Path;
 use std::process::Command;
+use std::time::Duration;
 use crate::app::GemaLauncherApp;
+use crate::diagnostic::{Diagnostic, DiagnosticKind, Severity};
+use crate::filename_rules::{FILENAME_RULES_PATH, VERSION_SUFFIXES_PATH};
+use crate::enrich::DatabaseRow;
+use crate::model::{share_errors, RightsHolder, Role, TrackField, TrackInfo, TrackOverride};
+use crate::parser::parse_track_filename;
+use crate::production::ProductionGroup;
+use crate::project::{with_project_extension, PROJECT_EXTENSION};
+use crate::report::{report_header, report_rows, report_total};
+use crate::settings::{
+    save_settings, CsvEncoding, CsvQuoting, DurationRounding, ReportMode, ShortUsage, SETTINGS_PATH,
+};
+use crate::timecode::{FrameRate, Timecode};
 use log::info;
 use rfd::FileDialog;
 use eframe::App;
-use webbrowser;
 use rusqlite::params;
 
+/// Hintergrund von Zellen mit Handänderungen
+const OVERRIDE_COLOR: egui::Color32 = egui::Color32::from_rgb(40, 70, 110);
+
 impl App for GemaLauncherApp {
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        // Keyboard shortcuts
-        if ctx.input(|i| i.key_pressed(egui::Key::Enter)) {
+        // Ergebnisse des Hintergrund-Einlesens übernehmen
+        self.poll_parse_job();
+        if self.parse_job.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+
+        // Keyboard shortcuts (Enter in a grid cell only confirms the cell)
+        if self.cell_edit.is_none() && ctx.input(|i| i.key_pressed(egui::Key::Enter)) {
             let _ = self.export_all_csv();
         }
+        // Undo/redo, unless a text field has focus and handles its own undo
+        if ctx.memory(|m| m.focus().is_none()) {
+            let redo_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z);
+            let undo_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+            let redo_y_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Y);
+            if ctx.input_mut(|i| i.consume_shortcut(&redo_shortcut) || i.consume_shortcut(&redo_y_shortcut)) {
+                self.redo();
+            } else if ctx.input_mut(|i| i.consume_shortcut(&undo_shortcut)) {
+                self.undo();
+            }
+        }
         
         // Process drag and drop
         let dropped_files = ctx.input(|input| input.raw.dropped_files.clone());
         if !dropped_files.is_empty() {
+            let mut paths = Vec::new();
             for file in dropped_files.iter() {
                 if let Some(path_str) = file.path.as_ref().and_then(|p| p.to_str()) {
                     info!("Datei per Drag-and-Drop hinzugefügt: {}", path_str);
-                    self.add_file(path_str.to_string());
+                    paths.push(path_str.to_string());
                 }
             }
-            let _ = self.parse_filenames();
+            self.add_files(paths);
         }
 
         // Top menu bar
         egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("Datei", |ui| {
+                    if ui.button("Projekt öffnen").clicked() {
+                        if let Some(path) = FileDialog::new()
+                            .add_filter("GEMA Projekt", &[PROJECT_EXTENSION])
+                            .pick_file()
+                        {
+                            self.open_project_file(&path.to_string_lossy());
+                        }
+                    }
+                    ui.menu_button("Zuletzt geöffnet", |ui| {
+                        if self.recent_projects.is_empty() {
+                            ui.label("Keine Projekte");
+                        }
+                        for path in self.recent_projects.clone() {
+                            let name = Path::new(&path).file_name().map_or(path.clone(), |n| n.to_string_lossy().to_string());
+                            if ui.button(name).on_hover_text(&path).clicked() {
+                                self.open_project_file(&path);
+                            }
+                        }
+                    });
+                    if ui.button("Projekt speichern").clicked() {
+                        match self.project_path.clone() {
+                            Some(path) => self.save_project_file(&path),
+                            None => self.save_project_as(),
+                        }
+                    }
+                    if ui.button("Projekt speichern unter").clicked() {
+                        self.save_project_as();
+                    }
+                    if ui.button("Geänderte Dateien neu einlesen").clicked() {
+                        self.parse_filenames();
+                    }
+                    ui.separator();
+
                     if ui.button("Exportieren").clicked() {
                         let _ = self.export_all_csv();
                     }
                     ui.menu_button("Datenbank", |ui| {
                         // Bestehende Buttons
                         if ui.button("Datensatz aktualisieren").clicked() {
-                            self.show_db_update_dialog = true;
+                            self.open_db_update_dialog();
                         }
                         if ui.button("Datenbank durchsuchen").clicked() {
                             self.show_db_search_dialog = true;
@@ -47,7 +114,10 @@ impl App for GemaLauncherApp {
                         ui.separator();
                         if ui.button("Datenbank optimieren").clicked() {
                             if let Err(e) = self.analyze_database() {
-                                self.error_messages.push(format!("Fehler bei Datenbankoptimierung: {}", e));
+                                self.report(Diagnostic::error(
+                                    DiagnosticKind::Database,
+                                    format!("Fehler bei Datenbankoptimierung: {}", e),
+                                ));
                             } else {
                                 rfd::MessageDialog::new()
                                     .set_title("Erfolg")
@@ -59,7 +129,10 @@ impl App for GemaLauncherApp {
                         
                         if ui.button("Datenbank komprimieren").clicked() {
                             if let Err(e) = self.vacuum_database() {
-                                self.error_messages.push(format!("Fehler bei Datenbankkomprimierung: {}", e));
+                                self.report(Diagnostic::error(
+                                    DiagnosticKind::Database,
+                                    format!("Fehler bei Datenbankkomprimierung: {}", e),
+                                ));
                             } else {
                                 rfd::MessageDialog::new()
                                     .set_title("Erfolg")
@@ -70,6 +143,18 @@ impl App for GemaLauncherApp {
                         }
                     });
 
+                    if ui.button("Einstellungen").clicked() {
+                        self.show_settings_dialog = true;
+                    }
+
+                    if ui.button("Dateinamen-Regeln").clicked() {
+                        self.show_filename_rules_dialog = true;
+                    }
+
+                    if ui.button("Produktionsdaten").clicked() {
+                        self.show_production_dialog = true;
+                    }
+
                     if ui.button("CSV Vorschau").clicked() {
                         self.show_csv_preview = !self.show_csv_preview;
                         if self.show_csv_preview && !self.filenames.is_empty() {
@@ -83,14 +168,39 @@ impl App for GemaLauncherApp {
                     }
                 });
 
+                ui.menu_button("Bearbeiten", |ui| {
+                    let undo_label = self.history.done().last().map(|e| format!("Rückgängig: {}", e.label));
+                    if ui
+                        .add_enabled(undo_label.is_some(), egui::Button::new(undo_label.unwrap_or("Rückgängig".to_string())))
+                        .on_hover_text("Strg+Z")
+                        .clicked()
+                    {
+                        self.undo();
+                    }
+                    let redo_label = self.history.undone().last().map(|e| format!("Wiederholen: {}", e.label));
+                    if ui
+                        .add_enabled(redo_label.is_some(), egui::Button::new(redo_label.unwrap_or("Wiederholen".to_string())))
+                        .on_hover_text("Strg+Y")
+                        .clicked()
+                    {
+                        self.redo();
+                    }
+                    ui.separator();
+                    if ui.button("Verlauf").clicked() {
+                        self.show_history_dialog = true;
+                    }
+                });
                 ui.menu_button("Datenbank", |ui| {
                     if ui.button("Datensatz aktualisieren").clicked() {
-                        self.show_db_update_dialog = true;
+                        self.open_db_update_dialog();
                     }
                     if ui.button("Datenbank durchsuchen").clicked() {
                         self.show_db_search_dialog = true;
                         self.db_search_results.clear();
                     }
+                    if ui.button("Vorschläge prüfen").clicked() {
+                        self.show_match_dialog = true;
+                    }
                 });
 
                 ui.menu_button("Hilfe", |ui| {
@@ -110,30 +220,49 @@ impl App for GemaLauncherApp {
                 
                         #[cfg(target_os = "linux")]
                         let _ = Command::new("xdg-email")
-                            .args(&["--subject", "Hilfe", "--body", "Hallo Tom,", "tom@example.com"])
+                            .args(["--subject", "Hilfe", "--body", "Hallo Tom,", "tom@example.com"])
                             .spawn();
                     }
                 });
             });
         });
 
+        // Progress of background parsing
+        if let Some(job) = &self.parse_job {
+            egui::TopBottomPanel::bottom("parse_progress").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let progress = &job.progress;
+                    let text = if job.is_cancelling() {
+                        "Wird abgebrochen …".to_string()
+                    } else if progress.total == 0 {
+                        "Starte …".to_string()
+                    } else {
+                        format!("{} ({}/{})", progress.stage, progress.done, progress.total)
+                    };
+                    ui.add(egui::ProgressBar::new(progress.fraction()).text(text).desired_width(400.0));
+                    if ui.add_enabled(!job.is_cancelling(), egui::Button::new("Abbrechen")).clicked() {
+                        job.cancel();
+                    }
+                });
+            });
+        }
+
         // Left side panel for actions
         egui::SidePanel::left("side_panel").resizable(true).min_width(200.0).show(ctx, |ui| {
             ui.add_space(5.0);
             ui.heading("Aktionen");
+            if let Some(name) = self.project_path.as_deref().and_then(|p| Path::new(p).file_name()) {
+                ui.label(format!("Projekt: {}", name.to_string_lossy()));
+            }
             ui.add_space(5.0);
 
             if ui.button("Dateien auswählen").clicked() {
                 if let Some(files) = FileDialog::new()
-                    .add_filter("Audio/Text Dateien", &["wav", "mp3", "txt"])
+                    .add_filter("Audio/Text Dateien", &["wav", "mp3", "txt", "edl", "fcpxml", "xml", "otio"])
                     .pick_files()
                 {
-                    for file in files {
-                        if let Some(path_str) = file.to_str() {
-                            self.add_file(path_str.to_string());
-                        }
-                    }
-                    let _ = self.parse_filenames();
+                    let paths = files.iter().filter_map(|file| file.to_str()).map(str::to_string).collect();
+                    self.add_files(paths);
                 }
             }
             ui.add_space(5.0);
@@ -196,7 +325,7 @@ impl App for GemaLauncherApp {
                 egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
                     let mut found = false;
                     
-                    for (_file, tracks) in &self.tracks_per_file {
+                    for tracks in self.filenames.iter().filter_map(|f| self.tracks_per_file.get(f)) {
                         for track in tracks {
                             if track.index.to_lowercase().contains(&query) || 
                                track.titel.to_lowercase().contains(&query) || 
@@ -210,7 +339,7 @@ impl App for GemaLauncherApp {
                                     ui.label(&track.titel);
                                     ui.label(&track.kuenstler);
                                     if let Some(duration) = track.duration {
-                                        ui.label(format!("{}", self.format_duration(duration)));
+                                        ui.label(self.format_duration(duration.to_seconds()));
                                     }
                                     ui.label(&track.label_code);
                                 });
@@ -245,16 +374,9 @@ impl App for GemaLauncherApp {
                 self.render_tracks_view(ui);
             }
 
-            // Error messages
-            if !self.error_messages.is_empty() {
-                ui.add_space(20.0);
-                ui.separator();
-                ui.colored_label(egui::Color32::RED, "Fehlerhafte Einträge:");
-                egui::ScrollArea::vertical().max_height(100.0).show(ui, |ui| {
-                    for error in &self.error_messages {
-                        ui.label(error);
-                    }
-                });
+            // Diagnostics
+            if !self.diagnostics.is_empty() {
+                self.render_diagnostics(ui);
             }
         });
 
@@ -267,13 +389,44 @@ impl App for GemaLauncherApp {
         if self.show_db_search_dialog {
             self.render_db_search_dialog(ctx);
         }
+
+        // Settings dialog
+        if self.show_settings_dialog {
+            self.render_settings_dialog(ctx);
+        }
+
+        // Filename rules dialog
+        if self.show_filename_rules_dialog {
+            self.render_filename_rules_dialog(ctx);
+        }
+
+        // Production metadata
+        if self.show_production_dialog {
+            self.render_production_dialog(ctx);
+        }
+
+        // Fuzzy database candidates
+        if self.show_match_dialog {
+            self.render_match_dialog(ctx);
+        }
+
+        // Track edit dialog
+        if self.track_edit.is_some() {
+            self.render_track_edit_dialog(ctx);
+        }
+
+        // Undo/redo history
+        if self.show_history_dialog {
+            self.render_history_dialog(ctx);
+        }
     }
 }
 
 // UI rendering methods implementation
 impl GemaLauncherApp {
     // Render the tracks overview
-    fn render_tracks_view(&self, ui: &mut egui::Ui) {
+    fn render_tracks_view(&mut self, ui: &mut egui::Ui) {
+        let mut rate_change = None;
         ui.collapsing("Geladene Dateien", |ui| {
             egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
                 for (i, filename) in self.filenames.iter().enumerate() {
@@ -285,27 +438,61 @@ impl GemaLauncherApp {
                                 // The actual modification would need to be handled elsewhere
                             }
                             ui.label(filename);
+
+                            // Bildrate je Datei: automatisch oder manuell gewählt
+                            let auto_rate = self.settings.frame_rate
+                                .or_else(|| self.detected_frame_rates.get(filename).copied())
+                                .unwrap_or(self.settings.fallback_frame_rate);
+                            let current = self.frame_rate_overrides.get(filename).copied();
+                            let mut choice = current;
+                            egui::ComboBox::from_id_source(format!("frame_rate_{}", filename))
+                                .selected_text(match current {
+                                    Some(rate) => format!("{} fps", rate),
+                                    None => format!("Auto ({} fps)", auto_rate),
+                                })
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut choice, None, format!("Auto ({} fps)", auto_rate));
+                                    for rate in FrameRate::ALL {
+                                        ui.selectable_value(&mut choice, Some(rate), format!("{} fps", rate));
+                                    }
+                                });
+                            if choice != current {
+                                rate_change = Some((filename.clone(), choice));
+                            }
                         }
                     });
                 }
             });
         });
 
+        if let Some((file, rate)) = rate_change {
+            self.set_frame_rate_override(&file, rate);
+        }
+
         ui.separator();
 
         // Tracks zählen
         let total_tracks: usize = self.tracks_per_file.values().map(|v| v.len()).sum();
         ui.heading(format!("Extrahierte Tracks: {}", total_tracks));
 
+        let mut scrolled = false;
+        let mut edit_request = None;
+        // Inline editing: the cell being edited is taken out of self while the grid borrows the tracks
+        let cell_edit = self.cell_edit.take();
+        let mut cell_text = std::mem::take(&mut self.cell_edit_text);
+        let mut start_cell_edit = None;
+        let mut finish_cell_edit = None;
+        let mut reset_cell = None;
         egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
-            for (file, tracks) in &self.tracks_per_file {
+            // Reihenfolge wie in der Dateiliste, Tracks in Programmreihenfolge
+            for (file, tracks) in self.filenames.iter().filter_map(|f| Some((f, self.tracks_per_file.get(f)?))) {
                 ui.group(|ui| {
                     ui.label(format!("Datei: {}", file));
                     ui.separator();
                     
                     // Create a grid for tabular layout
                     egui::Grid::new(format!("tracks_grid_{}", file))
-                        .num_columns(5)
+                        .num_columns(9)
                         .spacing([8.0, 4.0])
                         .striped(true)
                         .show(ui, |ui| {
@@ -313,21 +500,105 @@ impl GemaLauncherApp {
                             ui.strong("Index");
                             ui.strong("Titel");
                             ui.strong("Künstler");
+                            ui.strong("Version");
+                            ui.strong("Erstes In");
+                            ui.strong("Letztes Out");
                             ui.strong("Dauer");
                             ui.strong("Labelcode");
+                            ui.strong("ISRC");
                             ui.end_row();
                             
                             // Data rows
                             for track in tracks {
-                                ui.label(&track.index);
-                                ui.label(&track.titel);
-                                ui.label(&track.kuenstler);
-                                if let Some(dauer) = track.duration {
-                                    ui.label(self.format_duration(dauer));
-                                } else {
-                                    ui.label("-");
+                                // Sprungziel aus der Meldungsliste hervorheben
+                                let focused = self
+                                    .focused_track
+                                    .as_ref()
+                                    .is_some_and(|(f, index)| f == file && *index == track.index);
+                                // Von Hand geänderte Tracks kursiv markieren
+                                let edited = self
+                                    .track_overrides
+                                    .get(file)
+                                    .is_some_and(|overrides| overrides.contains_key(&track.key));
+                                let mut index_text = egui::RichText::new(&track.index);
+                                if edited {
+                                    index_text = index_text.italics();
+                                }
+                                if focused {
+                                    index_text = index_text
+                                        .strong()
+                                        .background_color(egui::Color32::from_rgb(120, 90, 0));
                                 }
-                                ui.label(&track.label_code);
+                                let response = ui
+                                    .add(egui::Label::new(index_text).sense(egui::Sense::click()))
+                                    .on_hover_text(if edited {
+                                        "Von Hand geändert – klicken zum Bearbeiten"
+                                    } else {
+                                        "Klicken zum Bearbeiten"
+                                    });
+                                if response.clicked() {
+                                    edit_request = Some((file.clone(), track.key.clone()));
+                                }
+                                if focused && self.scroll_to_focused_track {
+                                    response.scroll_to_me(Some(egui::Align::Center));
+                                    scrolled = true;
+                                }
+
+                                let track_override = self.track_overrides.get(file).and_then(|o| o.get(&track.key));
+                                let original = self.original_track(file, &track.key);
+                                let mut cell = |ui: &mut egui::Ui, field: TrackField| {
+                                    let editing = cell_edit
+                                        .as_ref()
+                                        .is_some_and(|(f, key, edited)| f == file && *key == track.key && *edited == field);
+                                    if editing {
+                                        let response = ui.add(egui::TextEdit::singleline(&mut cell_text).desired_width(160.0));
+                                        if response.lost_focus() {
+                                            // Escape discards the input, Enter or clicking elsewhere keeps it
+                                            let keep = !ui.input(|i| i.key_pressed(egui::Key::Escape));
+                                            finish_cell_edit = Some(keep);
+                                        } else if !response.has_focus() {
+                                            response.request_focus();
+                                        }
+                                        return;
+                                    }
+
+                                    let text = self.track_field_text(track, field);
+                                    let overridden = track_override.is_some_and(|o| o.is_set(field));
+                                    let mut rich_text = egui::RichText::new(if text.is_empty() { "-" } else { text.as_str() });
+                                    if overridden {
+                                        rich_text = rich_text.italics().background_color(OVERRIDE_COLOR);
+                                    }
+                                    let hover = match original.filter(|_| overridden) {
+                                        Some(original) => format!(
+                                            "Von Hand geändert, eingelesen: {}\nDoppelklick zum Bearbeiten, Rechtsklick zum Zurücksetzen",
+                                            self.track_field_text(original, field)
+                                        ),
+                                        None => "Doppelklick zum Bearbeiten".to_string(),
+                                    };
+                                    let response = ui
+                                        .add(egui::Label::new(rich_text).sense(egui::Sense::click()))
+                                        .on_hover_text(hover);
+                                    if response.double_clicked() {
+                                        start_cell_edit = Some((file.clone(), track.key.clone(), field, text));
+                                    }
+                                    if overridden {
+                                        response.context_menu(|ui| {
+                                            if ui.button(format!("{} zurücksetzen", field.label())).clicked() {
+                                                reset_cell = Some((file.clone(), track.key.clone(), field));
+                                                ui.close_menu();
+                                            }
+                                        });
+                                    }
+                                };
+                                cell(ui, TrackField::Titel);
+                                cell(ui, TrackField::Kuenstler);
+                                ui.label(track.versions_label());
+                                let timecode = |tc: Option<Timecode>| tc.map_or("-".to_string(), |tc| tc.to_string());
+                                ui.label(timecode(track.first_record_in));
+                                ui.label(timecode(track.last_record_out));
+                                cell(ui, TrackField::Duration);
+                                cell(ui, TrackField::LabelCode);
+                                ui.label(&track.isrc);
                                 ui.end_row();
                             }
                         });
@@ -335,6 +606,476 @@ impl GemaLauncherApp {
                 ui.separator();
             }
         });
+
+        if scrolled {
+            self.scroll_to_focused_track = false;
+        }
+        if let Some((file, key)) = edit_request {
+            self.open_track_edit(file, key);
+        }
+
+        self.cell_edit = cell_edit;
+        self.cell_edit_text = cell_text;
+        if let Some(keep) = finish_cell_edit {
+            if let Some((file, key, field)) = self.cell_edit.take() {
+                let text = std::mem::take(&mut self.cell_edit_text);
+                if keep {
+                    if let Err(e) = self.set_track_field(&file, &key, field, &text) {
+                        let index = self.original_track(&file, &key).map(|t| t.index.clone()).unwrap_or_default();
+                        self.report(
+                            Diagnostic::warning(DiagnosticKind::InvalidTimecode, format!("{:#}", e))
+                                .file(&file)
+                                .track(&index),
+                        );
+                    }
+                }
+            }
+        }
+        if let Some((file, key, field, text)) = start_cell_edit {
+            self.cell_edit = Some((file, key, field));
+            self.cell_edit_text = text;
+        }
+        if let Some((file, key, field)) = reset_cell {
+            self.clear_track_field(&file, &key, field);
+        }
+    }
+
+    /// Cell text of an editable field, as shown in the tracks grid
+    fn track_field_text(&self, track: &TrackInfo, field: TrackField) -> String {
+        match field {
+            TrackField::Titel => track.titel.clone(),
+            TrackField::Kuenstler => track.kuenstler.clone(),
+            TrackField::LabelCode => track.label_code.clone(),
+            TrackField::Duration => track.duration.map_or(String::new(), |d| self.format_duration(d.to_seconds())),
+        }
+    }
+
+    fn open_track_edit(&mut self, file: String, key: String) {
+        let Some(track) = self
+            .tracks_per_file
+            .get(&file)
+            .and_then(|tracks| tracks.iter().find(|t| t.key == key))
+        else {
+            return;
+        };
+        self.track_edit_values = TrackOverride {
+            titel: Some(track.titel.clone()),
+            kuenstler: Some(track.kuenstler.clone()),
+            label_code: Some(track.label_code.clone()),
+            isrc: Some(track.isrc.clone()),
+            rights_holders: Some(track.rights_holders.clone()),
+            duration: None,
+        };
+        self.track_edit = Some((file, key));
+    }
+
+    // Render the production metadata form and the file assignment
+    fn render_production_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_production_dialog;
+        let mut assign = None;
+        egui::Window::new("Produktionsdaten")
+            .open(&mut open)
+            .resizable(true)
+            .min_width(450.0)
+            .show(ctx, |ui| {
+                ui.label("Die Angaben erscheinen als Kopfblock in Vorschau und Export jeder zugeordneten Datei.");
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Produktion:");
+                    let selected_name = self
+                        .productions
+                        .get(self.selected_production)
+                        .map_or("Keine", |group| group.name.as_str())
+                        .to_string();
+                    egui::ComboBox::from_id_source("production_group")
+                        .selected_text(selected_name)
+                        .show_ui(ui, |ui| {
+                            for (i, group) in self.productions.iter().enumerate() {
+                                ui.selectable_value(&mut self.selected_production, i, &group.name);
+                            }
+                        });
+
+                    if ui.button("Neue Produktion").clicked() {
+                        // Dateien ohne Produktion gleich zuordnen
+                        let files = self
+                            .filenames
+                            .iter()
+                            .filter(|f| self.production_for(f).is_none())
+                            .cloned()
+                            .collect();
+                        self.productions.push(ProductionGroup {
+                            name: format!("Produktion {}", self.productions.len() + 1),
+                            files,
+                            ..Default::default()
+                        });
+                        self.selected_production = self.productions.len() - 1;
+                    }
+
+                    if ui.button("Produktion löschen").clicked() && self.selected_production < self.productions.len() {
+                        self.productions.remove(self.selected_production);
+                        self.selected_production = 0;
+                    }
+                });
+
+                let filenames = &self.filenames;
+                let selected = self.selected_production;
+                let (current, others) = match self.productions.get_mut(selected) {
+                    Some(_) => {
+                        let (before, rest) = self.productions.split_at_mut(selected);
+                        let (current, after) = rest.split_first_mut().expect("Gruppe vorhanden");
+                        (current, before.iter().chain(after.iter()).collect::<Vec<_>>())
+                    }
+                    None => return,
+                };
+
+                ui.add_space(5.0);
+                egui::Grid::new("production_fields")
+                    .num_columns(2)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Bezeichnung:");
+                        ui.text_edit_singleline(&mut current.name);
+                        ui.end_row();
+                        for (label, value) in current.info.fields_mut() {
+                            ui.label(format!("{}:", label));
+                            ui.text_edit_singleline(value);
+                            ui.end_row();
+                        }
+                    });
+
+                ui.add_space(10.0);
+                ui.strong("Dateien");
+                if filenames.is_empty() {
+                    ui.label("Keine Dateien geladen.");
+                }
+                for file in filenames {
+                    let mut member = current.files.contains(file);
+                    ui.horizontal(|ui| {
+                        if ui.checkbox(&mut member, file).changed() {
+                            assign = Some((file.clone(), member.then_some(selected)));
+                        }
+                        if let Some(other) = others.iter().find(|g| g.files.contains(file)) {
+                            ui.weak(format!("(bisher: {})", other.name));
+                        }
+                    });
+                }
+            });
+        self.show_production_dialog = open;
+
+        if let Some((file, group)) = assign {
+            self.assign_production(&file, group);
+        }
+    }
+
+    fn open_project_file(&mut self, path: &str) {
+        if let Err(e) = self.open_project(path) {
+            self.report(
+                Diagnostic::error(DiagnosticKind::File, format!("Projekt konnte nicht geöffnet werden: {:#}", e))
+                    .file(path),
+            );
+        }
+    }
+
+    fn save_project_file(&mut self, path: &str) {
+        if let Err(e) = self.save_project(path) {
+            self.report(
+                Diagnostic::error(DiagnosticKind::File, format!("Projekt konnte nicht gespeichert werden: {:#}", e))
+                    .file(path),
+            );
+        }
+    }
+
+    fn save_project_as(&mut self) {
+        let file_name = self
+            .project_path
+            .as_deref()
+            .and_then(|p| Path::new(p).file_name())
+            .map_or_else(|| format!("Projekt.{}", PROJECT_EXTENSION), |n| n.to_string_lossy().to_string());
+        if let Some(path) = FileDialog::new()
+            .add_filter("GEMA Projekt", &[PROJECT_EXTENSION])
+            .set_file_name(&file_name)
+            .save_file()
+        {
+            self.save_project_file(&with_project_extension(&path));
+        }
+    }
+
+    // Render the undo/redo history; clicking an entry undoes or redoes up to it
+    fn render_history_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_history_dialog;
+        let mut undo_steps = 0;
+        let mut redo_steps = 0;
+        egui::Window::new("Verlauf")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(350.0)
+            .show(ctx, |ui| {
+                let done = self.history.done();
+                let undone = self.history.undone();
+                if done.is_empty() && undone.is_empty() {
+                    ui.label("Noch keine Änderungen.");
+                    return;
+                }
+                ui.label("Klick auf einen Eintrag stellt den Stand nach dieser Änderung her.");
+                ui.add_space(5.0);
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for (i, entry) in done.iter().enumerate() {
+                        let current = i + 1 == done.len();
+                        let text = egui::RichText::new(&entry.label);
+                        if ui.selectable_label(current, if current { text.strong() } else { text }).clicked() {
+                            undo_steps = done.len() - i - 1;
+                        }
+                    }
+                    // Rückgängig gemachte Änderungen, als nächstes wiederholbare zuerst
+                    for (i, entry) in undone.iter().enumerate().rev() {
+                        let text = egui::RichText::new(&entry.label).weak().italics();
+                        if ui.selectable_label(false, text).clicked() {
+                            redo_steps = undone.len() - i;
+                        }
+                    }
+                });
+            });
+        self.show_history_dialog = open;
+
+        // Stop at the first step that fails, later steps would build on a wrong state
+        for _ in 0..undo_steps {
+            if !self.undo() {
+                break;
+            }
+        }
+        for _ in 0..redo_steps {
+            if !self.redo() {
+                break;
+            }
+        }
+    }
+
+    // Render fuzzy database candidates for tracks without an exact match
+    fn render_match_dialog(&mut self, ctx: &egui::Context) {
+        let pending = self.pending_matches();
+        let mut open = self.show_match_dialog;
+        let mut confirm = None;
+        let mut dismiss = None;
+        egui::Window::new("Datenbank-Vorschläge")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(650.0)
+            .show(ctx, |ui| {
+                if pending.is_empty() {
+                    ui.label("Keine offenen Vorschläge.");
+                    return;
+                }
+                ui.label(format!(
+                    "Für diese Tracks gibt es keinen exakten Index-Treffer. Vorschläge ab {:.0} % Konfidenz werden automatisch übernommen.",
+                    self.settings.fuzzy_match_threshold * 100.0
+                ));
+                ui.add_space(5.0);
+                egui::ScrollArea::vertical().max_height(450.0).show(ui, |ui| {
+                    let mut shown = Vec::new();
+                    for (file, found) in &pending {
+                        // gleicher Index in mehreren Dateien: nur einmal anzeigen
+                        if shown.contains(&&found.index) {
+                            continue;
+                        }
+                        shown.push(&found.index);
+                        ui.group(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.strong(&found.index);
+                                if !found.titel.is_empty() || !found.kuenstler.is_empty() {
+                                    ui.label(format!("{} – {}", found.titel, found.kuenstler));
+                                }
+                            });
+                            ui.label(format!("Datei: {}", file));
+                            egui::Grid::new(format!("candidates_{}_{}", file, found.index))
+                                .num_columns(6)
+                                .spacing([8.0, 4.0])
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    ui.strong("Konfidenz");
+                                    ui.strong("DB-Index");
+                                    ui.strong("Titel");
+                                    ui.strong("Künstler");
+                                    ui.strong("Labelcode");
+                                    ui.label("");
+                                    ui.end_row();
+                                    for candidate in &found.candidates {
+                                        ui.label(format!("{:.0} %", candidate.confidence * 100.0));
+                                        ui.label(&candidate.index);
+                                        ui.label(&candidate.titel);
+                                        ui.label(&candidate.kuenstler);
+                                        ui.label(&candidate.label_code);
+                                        if ui.button("Übernehmen").clicked() {
+                                            confirm = Some((found.index.clone(), candidate.clone()));
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                            if ui.button("Keiner passt").clicked() {
+                                dismiss = Some(found.index.clone());
+                            }
+                        });
+                    }
+                });
+            });
+        self.show_match_dialog = open;
+
+        if let Some((index, candidate)) = confirm {
+            self.confirm_candidate(&index, &candidate);
+        }
+        if let Some(index) = dismiss {
+            self.dismiss_candidates(&index);
+        }
+    }
+
+    // Render the window for hand edits of a single track
+    fn render_track_edit_dialog(&mut self, ctx: &egui::Context) {
+        let Some((file, key)) = self.track_edit.clone() else {
+            return;
+        };
+        let original = self.original_track(&file, &key).cloned();
+        let mut open = true;
+        let mut action = None;
+        egui::Window::new(format!("Track bearbeiten: {}", original.as_ref().map_or("", |t| t.index.as_str())))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("Datei: {}", file));
+                ui.label("Änderungen bleiben beim erneuten Einlesen und nach dem Datenbank-Abgleich erhalten.");
+                ui.label("Die Dauer wird in der Track-Übersicht bearbeitet und bleibt hier unverändert.");
+                ui.add_space(5.0);
+                let values = &mut self.track_edit_values;
+                egui::Grid::new("track_edit_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("Titel:");
+                    ui.text_edit_singleline(values.titel.get_or_insert_with(String::new));
+                    ui.end_row();
+                    ui.label("Künstler:");
+                    ui.text_edit_singleline(values.kuenstler.get_or_insert_with(String::new));
+                    ui.end_row();
+                    ui.label("Labelcode:");
+                    ui.text_edit_singleline(values.label_code.get_or_insert_with(String::new));
+                    ui.end_row();
+                    ui.label("ISRC:");
+                    ui.text_edit_singleline(values.isrc.get_or_insert_with(String::new));
+                    ui.end_row();
+                });
+                ui.add_space(5.0);
+                ui.strong("Beteiligte");
+                let rights_holders = values.rights_holders.get_or_insert_with(Vec::new);
+                rights_holders_editor(ui, "track_edit_rights", rights_holders);
+                for error in share_errors(rights_holders) {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Übernehmen").clicked() {
+                        action = Some(true);
+                    }
+                    if ui.button("Änderungen verwerfen").clicked() {
+                        action = Some(false);
+                    }
+                });
+            });
+
+        match action {
+            Some(true) => {
+                // Same path as the inline cells: only the dialog's fields change, the duration stays
+                let values = std::mem::take(&mut self.track_edit_values);
+                let track_override = self.edited_override(&file, &key, |o| {
+                    o.titel = values.titel;
+                    o.kuenstler = values.kuenstler;
+                    o.label_code = values.label_code;
+                    o.isrc = values.isrc;
+                    o.rights_holders = values.rights_holders;
+                });
+                self.set_track_override(&file, &key, track_override);
+                self.track_edit = None;
+            }
+            Some(false) => {
+                let track_override = self.edited_override(&file, &key, |o| {
+                    *o = TrackOverride { duration: o.duration, ..TrackOverride::default() };
+                });
+                self.set_track_override(&file, &key, track_override);
+                self.track_edit = None;
+            }
+            None if !open => self.track_edit = None,
+            None => {}
+        }
+    }
+
+    // Render diagnostics grouped by file, with severity filter and jump to track
+    fn render_diagnostics(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(20.0);
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.strong("Meldungen:");
+            for severity in Severity::ALL {
+                let count = self.diagnostics.iter().filter(|d| d.severity == severity).count();
+                let mut visible = !self.hidden_severities.contains(&severity);
+                let text = egui::RichText::new(format!("{} {}", count, severity.label())).color(severity_color(severity));
+                if ui.checkbox(&mut visible, text).changed() {
+                    if visible {
+                        self.hidden_severities.retain(|s| *s != severity);
+                    } else {
+                        self.hidden_severities.push(severity);
+                    }
+                }
+            }
+        });
+
+        // Nach Datei gruppieren, in der Reihenfolge des ersten Auftretens
+        let mut groups: Vec<(Option<&str>, Vec<&Diagnostic>)> = Vec::new();
+        for diagnostic in self.diagnostics.iter().filter(|d| !self.hidden_severities.contains(&d.severity)) {
+            let file = diagnostic.file.as_deref();
+            match groups.iter_mut().find(|(f, _)| *f == file) {
+                Some((_, list)) => list.push(diagnostic),
+                None => groups.push((file, vec![diagnostic])),
+            }
+        }
+
+        let mut jump_to = None;
+        egui::ScrollArea::vertical()
+            .id_source("diagnostics_scroll")
+            .max_height(150.0)
+            .show(ui, |ui| {
+                for (file, list) in &groups {
+                    let title = format!("{} ({})", file.unwrap_or("Allgemein"), list.len());
+                    egui::CollapsingHeader::new(title)
+                        .id_source(format!("diagnostics_{}", file.unwrap_or_default()))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for diagnostic in list {
+                                ui.horizontal(|ui| {
+                                    ui.colored_label(severity_color(diagnostic.severity), diagnostic.severity.label());
+                                    ui.label(format!("[{}]", diagnostic.kind.label()));
+                                    if let Some(line) = diagnostic.line {
+                                        ui.label(format!("Zeile {}", line));
+                                    }
+                                    ui.label(&diagnostic.message);
+                                    if let (Some(file), Some(index)) = (&diagnostic.file, &diagnostic.track_index) {
+                                        if ui.link("Zum Track").clicked() {
+                                            jump_to = Some((file.clone(), index.clone()));
+                                        }
+                                    }
+                                });
+                                ui.indent("diagnostic_details", |ui| {
+                                    if let Some(raw) = &diagnostic.raw_line {
+                                        ui.monospace(raw);
+                                    }
+                                    if let Some(suggestion) = &diagnostic.suggestion {
+                                        ui.weak(format!("Vorschlag: {}", suggestion));
+                                    }
+                                });
+                            }
+                        });
+                }
+            });
+
+        if let Some(target) = jump_to {
+            self.show_csv_preview = false;
+            self.focused_track = Some(target);
+            self.scroll_to_focused_track = true;
+        }
     }
 
     // Render CSV preview
@@ -362,6 +1103,20 @@ impl GemaLauncherApp {
         // CSV table preview
         if let Some(selected_file) = &self.selected_csv_file {
             if let Some(tracks) = self.tracks_per_file.get(selected_file) {
+                // Kopfblock der Produktion, wie im Export
+                if let Some(production) = self.production_for(selected_file).filter(|p| !p.is_empty()) {
+                    egui::Grid::new("csv_preview_production")
+                        .num_columns(2)
+                        .spacing([20.0, 2.0])
+                        .show(ui, |ui| {
+                            for (label, value) in production.fields() {
+                                ui.strong(label);
+                                ui.label(value);
+                                ui.end_row();
+                            }
+                        });
+                    ui.add_space(5.0);
+                }
                 ui.group(|ui| {
                     // Add a scroll area for both horizontal and vertical scrolling
                     egui::ScrollArea::both()
@@ -377,31 +1132,51 @@ impl GemaLauncherApp {
                                 .show(ui, |ui| {
                                     ui.allocate_space(egui::Vec2::new(min_content_width, 0.0));
                                     
+                                    let mode = self.settings.report_mode;
+                                    let excel_text = self.settings.export_profile().excel_text_duration;
+                                    let header = report_header(mode);
+
                                     // Use Grid for tabular layout
                                     egui::Grid::new("csv_preview_grid")
-                                        .num_columns(5)
+                                        .num_columns(header.len())
                                         .spacing([20.0, 6.0]) // More spacing between columns
                                         .striped(true)
                                         .show(ui, |ui| {
                                             // Header row with extra spacing
-                                            ui.strong("Index");
-                                            ui.strong("Titel");
-                                            ui.strong("Künstler");
-                                            ui.strong("Dauer");
-                                            ui.strong("Labelcode");
+                                            for column in &header {
+                                                ui.strong(*column);
+                                            }
                                             ui.end_row();
                                             
                                             // Data rows - ensure long content doesn't get truncated
-                                            for track in tracks {
-                                                ui.label(&track.index);
-                                                ui.label(&track.titel);
-                                                ui.label(&track.kuenstler);
-                                                if let Some(dauer) = track.duration {
-                                                    ui.label(format!("' {}", self.format_duration(dauer)));
-                                                } else {
-                                                    ui.label("-");
+                                            let policy = self.settings.duration_policy();
+                                            let rows = report_rows(tracks, mode, &policy);
+                                            let overrides = self.track_overrides.get(selected_file);
+                                            for row in &rows {
+                                                let track_override = overrides.and_then(|o| o.get(&row.track.key));
+                                                let cells = self.report_cells(row, mode, excel_text);
+                                                for (column, cell) in header.iter().zip(cells) {
+                                                    // Handänderungen wie in der Track-Übersicht markieren
+                                                    let field = match *column {
+                                                        "Titel" => Some(TrackField::Titel),
+                                                        "Künstler" => Some(TrackField::Kuenstler),
+                                                        "Labelcode" => Some(TrackField::LabelCode),
+                                                        "Dauer" => Some(TrackField::Duration),
+                                                        _ => None,
+                                                    };
+                                                    if field.is_some_and(|f| track_override.is_some_and(|o| o.is_set(f))) {
+                                                        ui.label(egui::RichText::new(cell).italics().background_color(OVERRIDE_COLOR));
+                                                    } else {
+                                                        ui.label(cell);
+                                                    }
+                                                }
+                                                ui.end_row();
+                                            }
+                                            if policy.total_line {
+                                                let total = report_total(&rows);
+                                                for cell in self.report_total_cells(total, mode, excel_text) {
+                                                    ui.strong(cell);
                                                 }
-                                                ui.label(&track.label_code);
                                                 ui.end_row();
                                             }
                                         });
@@ -416,8 +1191,490 @@ impl GemaLauncherApp {
         }
     }
 
+    // Render settings dialog
+    fn render_settings_dialog(&mut self, ctx: &egui::Context) {
+        let mut reparse = false;
+        egui::Window::new("Einstellungen")
+            .resizable(true)
+            .min_width(400.0)
+            .show(ctx, |ui| {
+                ui.heading("Timecode");
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Bildrate:");
+                    let before = self.settings.frame_rate;
+                    egui::ComboBox::from_id_source("settings_frame_rate")
+                        .selected_text(match self.settings.frame_rate {
+                            Some(rate) => format!("{} fps", rate),
+                            None => "Automatisch erkennen".to_string(),
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.settings.frame_rate, None, "Automatisch erkennen");
+                            for rate in FrameRate::ALL {
+                                ui.selectable_value(&mut self.settings.frame_rate, Some(rate), format!("{} fps", rate));
+                            }
+                        });
+                    reparse |= before != self.settings.frame_rate;
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Falls nicht erkannt:");
+                    let before = self.settings.fallback_frame_rate;
+                    egui::ComboBox::from_id_source("settings_fallback_frame_rate")
+                        .selected_text(format!("{} fps", self.settings.fallback_frame_rate))
+                        .show_ui(ui, |ui| {
+                            for rate in FrameRate::ALL {
+                                ui.selectable_value(&mut self.settings.fallback_frame_rate, rate, format!("{} fps", rate));
+                            }
+                        });
+                    reparse |= before != self.settings.fallback_frame_rate;
+                });
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
+                ui.heading("Tracklisten Import");
+                ui.add_space(5.0);
+
+                egui::Grid::new("text_columns_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 6.0])
+                    .show(ui, |ui| {
+                        let columns = &mut self.settings.text_columns;
+                        for (label, value) in [
+                            ("Spalte Dauer:", &mut columns.duration),
+                            ("Spalte Name:", &mut columns.clip_name),
+                            ("Spalte Start:", &mut columns.record_in),
+                        ] {
+                            ui.label(label);
+                            reparse |= ui.text_edit_singleline(value).lost_focus();
+                            ui.end_row();
+                        }
+                    });
+                ui.label("Spaltenname aus der Kopfzeile oder Nummer (1 = erste Spalte); leer = automatisch");
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
+                ui.heading("EDL Import");
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Audiokanäle:");
+                    let response = ui.text_edit_singleline(&mut self.settings.edl_audio_channels);
+                    reparse |= response.lost_focus();
+                });
+                ui.label("z.B. \"1-4, 7\"; leer = alle Audiokanäle");
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
+                ui.heading("Pro Tools Import");
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Musikspuren:");
+                    let response = ui.text_edit_singleline(&mut self.settings.protools_music_tracks);
+                    reparse |= response.lost_focus();
+                });
+                ui.label("Spurnamen, z.B. \"MX*, Musik*\"; '*' steht für beliebige Zeichen");
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
+                ui.heading("Ausgabe");
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Einträge:");
+                    egui::ComboBox::from_id_source("settings_report_mode")
+                        .selected_text(self.settings.report_mode.label())
+                        .show_ui(ui, |ui| {
+                            for mode in ReportMode::ALL {
+                                ui.selectable_value(&mut self.settings.report_mode, mode, mode.label());
+                            }
+                        });
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Lückentoleranz:");
+                    let response = ui.add(
+                        egui::DragValue::new(&mut self.settings.gap_tolerance_seconds)
+                            .clamp_range(0.0..=60.0)
+                            .speed(0.1)
+                            .suffix(" s"),
+                    );
+                    reparse |= response.drag_released() || response.lost_focus();
+                });
+                ui.label("Verwendungen desselben Tracks mit kürzerer Lücke gelten als eine");
+
+                ui.horizontal(|ui| {
+                    ui.label("Unscharfe Treffer übernehmen ab:");
+                    let response = ui.add(
+                        egui::DragValue::new(&mut self.settings.fuzzy_match_threshold)
+                            .clamp_range(0.5..=1.0)
+                            .speed(0.01)
+                            .custom_formatter(|value, _| format!("{:.0} %", value * 100.0))
+                            .custom_parser(|text| {
+                                text.trim_end_matches('%').trim().parse::<f64>().ok().map(|v| v / 100.0)
+                            }),
+                    );
+                    reparse |= response.drag_released() || response.lost_focus();
+                });
+                ui.label("Tracks ohne exakten Index-Treffer; darunter erscheinen Vorschläge zur Bestätigung");
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
+                ui.heading("Dauer-Regel");
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Regel:");
+                    egui::ComboBox::from_id_source("settings_duration_policy")
+                        .selected_text(&self.settings.active_duration_policy)
+                        .show_ui(ui, |ui| {
+                            for policy in &self.settings.duration_policies {
+                                if ui
+                                    .selectable_value(
+                                        &mut self.settings.active_duration_policy,
+                                        policy.name.clone(),
+                                        &policy.name,
+                                    )
+                                    .changed()
+                                {
+                                    self.policy_name_edit = None;
+                                }
+                            }
+                        });
+
+                    if ui.button("Neue Regel").clicked() {
+                        let mut policy = self.settings.duration_policy();
+                        let names: Vec<String> = self.settings.duration_policies.iter().map(|p| p.name.clone()).collect();
+                        policy.name = unused_name("Regel", &names);
+                        self.settings.active_duration_policy = policy.name.clone();
+                        self.settings.duration_policies.push(policy);
+                        self.policy_name_edit = None;
+                    }
+
+                    if ui.button("Regel löschen").clicked() && self.settings.duration_policies.len() > 1 {
+                        let active = self.settings.active_duration_policy.clone();
+                        self.settings.duration_policies.retain(|p| p.name != active);
+                        self.settings.active_duration_policy = self.settings.duration_policies[0].name.clone();
+                        self.policy_name_edit = None;
+                    }
+                });
+
+                let active = self.settings.active_duration_policy.clone();
+                let other_names: Vec<String> = self
+                    .settings
+                    .duration_policies
+                    .iter()
+                    .map(|p| p.name.clone())
+                    .filter(|name| *name != active)
+                    .collect();
+                if let Some(policy) = self.settings.duration_policies.iter_mut().find(|p| p.name == active) {
+                    egui::Grid::new("duration_policy_grid")
+                        .num_columns(2)
+                        .spacing([10.0, 6.0])
+                        .show(ui, |ui| {
+                            ui.label("Name:");
+                            if unique_name_editor(ui, &mut self.policy_name_edit, &mut policy.name, &other_names) {
+                                self.settings.active_duration_policy = policy.name.clone();
+                            }
+                            ui.end_row();
+
+                            ui.label("Rundung je Verwendung:");
+                            egui::ComboBox::from_id_source("settings_duration_rounding")
+                                .selected_text(policy.rounding.label())
+                                .show_ui(ui, |ui| {
+                                    for rounding in DurationRounding::ALL {
+                                        ui.selectable_value(&mut policy.rounding, rounding, rounding.label());
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("Mindestdauer:");
+                            ui.add(
+                                egui::DragValue::new(&mut policy.min_usage_seconds)
+                                    .clamp_range(0.0..=60.0)
+                                    .speed(0.1)
+                                    .suffix(" s"),
+                            );
+                            ui.end_row();
+
+                            ui.label("Kürzere Verwendungen:");
+                            egui::ComboBox::from_id_source("settings_short_usage")
+                                .selected_text(policy.short_usage.label())
+                                .show_ui(ui, |ui| {
+                                    for short_usage in ShortUsage::ALL {
+                                        ui.selectable_value(&mut policy.short_usage, short_usage, short_usage.label());
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("Summenzeile:");
+                            ui.checkbox(&mut policy.total_line, "Gesamtdauer am Ende ausgeben");
+                            ui.end_row();
+                        });
+                }
+                ui.label("Gilt für CSV-Vorschau und Export; die Mindestdauer wird vor dem Runden geprüft");
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
+                ui.heading("CSV Export");
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Profil:");
+                    egui::ComboBox::from_id_source("settings_export_profile")
+                        .selected_text(&self.settings.active_export_profile)
+                        .show_ui(ui, |ui| {
+                            for profile in &self.settings.export_profiles {
+                                if ui
+                                    .selectable_value(
+                                        &mut self.settings.active_export_profile,
+                                        profile.name.clone(),
+                                        &profile.name,
+                                    )
+                                    .changed()
+                                {
+                                    self.profile_name_edit = None;
+                                }
+                            }
+                        });
+
+                    if ui.button("Neues Profil").clicked() {
+                        let mut profile = self.settings.export_profile();
+                        let names: Vec<String> = self.settings.export_profiles.iter().map(|p| p.name.clone()).collect();
+                        profile.name = unused_name("Profil", &names);
+                        self.settings.active_export_profile = profile.name.clone();
+                        self.settings.export_profiles.push(profile);
+                        self.profile_name_edit = None;
+                    }
+
+                    if ui.button("Profil löschen").clicked() && self.settings.export_profiles.len() > 1 {
+                        let active = self.settings.active_export_profile.clone();
+                        self.settings.export_profiles.retain(|p| p.name != active);
+                        self.settings.active_export_profile = self.settings.export_profiles[0].name.clone();
+                        self.profile_name_edit = None;
+                    }
+                });
+
+                let active = self.settings.active_export_profile.clone();
+                let other_names: Vec<String> = self
+                    .settings
+                    .export_profiles
+                    .iter()
+                    .map(|p| p.name.clone())
+                    .filter(|name| *name != active)
+                    .collect();
+                if let Some(profile) = self.settings.export_profiles.iter_mut().find(|p| p.name == active) {
+                    egui::Grid::new("export_profile_grid")
+                        .num_columns(2)
+                        .spacing([10.0, 6.0])
+                        .show(ui, |ui| {
+                            ui.label("Name:");
+                            if unique_name_editor(ui, &mut self.profile_name_edit, &mut profile.name, &other_names) {
+                                self.settings.active_export_profile = profile.name.clone();
+                            }
+                            ui.end_row();
+
+                            ui.label("Trennzeichen:");
+                            egui::ComboBox::from_id_source("settings_export_delimiter")
+                                .selected_text(delimiter_label(profile.delimiter))
+                                .show_ui(ui, |ui| {
+                                    for delimiter in [',', ';', '\t', '|'] {
+                                        ui.selectable_value(&mut profile.delimiter, delimiter, delimiter_label(delimiter));
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("Kodierung:");
+                            egui::ComboBox::from_id_source("settings_export_encoding")
+                                .selected_text(profile.encoding.label())
+                                .show_ui(ui, |ui| {
+                                    for encoding in CsvEncoding::ALL {
+                                        ui.selectable_value(&mut profile.encoding, encoding, encoding.label());
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("Anführungszeichen:");
+                            egui::ComboBox::from_id_source("settings_export_quoting")
+                                .selected_text(profile.quoting.label())
+                                .show_ui(ui, |ui| {
+                                    for quoting in CsvQuoting::ALL {
+                                        ui.selectable_value(&mut profile.quoting, quoting, quoting.label());
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("Excel:");
+                            ui.checkbox(&mut profile.excel_text_duration, "Dauer als Text (' 12:34)");
+                            ui.end_row();
+                        });
+                }
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Speichern").clicked() {
+                        if let Err(e) = save_settings(SETTINGS_PATH, &self.settings) {
+                            self.report(
+                                Diagnostic::error(
+                                    DiagnosticKind::Config,
+                                    format!("Einstellungen konnten nicht gespeichert werden: {}", e),
+                                )
+                                .file(SETTINGS_PATH),
+                            );
+                        }
+                    }
+
+                    if ui.button("Schließen").clicked() {
+                        self.show_settings_dialog = false;
+                    }
+                });
+            });
+
+        if reparse {
+            self.parse_filenames();
+        }
+    }
+
+    // Render filename rules dialog with a test field
+    fn render_filename_rules_dialog(&mut self, ctx: &egui::Context) {
+        let mut reload = false;
+        egui::Window::new("Dateinamen-Regeln")
+            .resizable(true)
+            .min_width(500.0)
+            .show(ctx, |ui| {
+                ui.label(format!("Regeln aus {} (höhere Priorität zuerst):", FILENAME_RULES_PATH));
+                ui.add_space(5.0);
+
+                egui::Grid::new("filename_rules_grid")
+                    .num_columns(4)
+                    .spacing([10.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Priorität");
+                        ui.strong("Name");
+                        ui.strong("Präfix");
+                        ui.strong("Muster");
+                        ui.end_row();
+
+                        for rule in self.filename_rules.rules() {
+                            ui.label(rule.priority.to_string());
+                            ui.label(&rule.name);
+                            ui.label(if rule.prefix.is_empty() { "alle" } else { rule.prefix.as_str() });
+                            ui.monospace(&rule.pattern);
+                            ui.end_row();
+                        }
+                    });
+
+                for error in &self.filename_rules.errors {
+                    ui.colored_label(egui::Color32::RED, &error.message);
+                }
+
+                ui.add_space(5.0);
+                let suffixes: Vec<&str> = self.filename_rules.versions.suffixes().collect();
+                ui.label(format!("Versionsendungen aus {}:", VERSION_SUFFIXES_PATH));
+                ui.label(if suffixes.is_empty() { "keine".to_string() } else { suffixes.join(", ") });
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
+                ui.heading("Test");
+                ui.horizontal(|ui| {
+                    ui.label("Clipname:");
+                    ui.text_edit_singleline(&mut self.filename_rule_test);
+                });
+
+                if !self.filename_rule_test.trim().is_empty() {
+                    let result = parse_track_filename(&self.filename_rules, self.filename_rule_test.trim());
+                    let label_code = self
+                        .label_codes
+                        .lookup(&result.index)
+                        .map(|m| format!("{} (Präfix '{}')", m.code, m.prefix))
+                        .unwrap_or_else(|| "-".to_string());
+
+                    egui::Grid::new("filename_rule_test_grid")
+                        .num_columns(2)
+                        .spacing([10.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Regel:");
+                            if result.rule.is_empty() {
+                                ui.label("keine passende Regel, Standardzerlegung");
+                            } else {
+                                ui.strong(&result.rule);
+                            }
+                            ui.end_row();
+                            for (label, value) in [
+                                ("Index:", &result.index),
+                                ("Titel:", &result.title),
+                                ("Künstler:", &result.artist),
+                                ("Version:", &result.version),
+                                ("Labelcode:", &label_code),
+                            ] {
+                                ui.label(label);
+                                ui.label(value);
+                                ui.end_row();
+                            }
+                        });
+                }
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Neu laden").clicked() {
+                        reload = true;
+                    }
+                    if ui.button("Schließen").clicked() {
+                        self.show_filename_rules_dialog = false;
+                    }
+                });
+            });
+
+        if reload {
+            self.reload_filename_rules();
+            self.parse_filenames();
+        }
+    }
+
+    // Open the database update dialog; rights holders are loaded for the index on the next frame
+    fn open_db_update_dialog(&mut self) {
+        self.db_update_rights_index = None;
+        self.db_update_rights_edited = false;
+        self.show_db_update_dialog = true;
+    }
+
+    // Load the rights holders of the typed index unless the user already edited the list
+    fn sync_db_update_rights(&mut self) {
+        let index = self.db_update_index.trim().to_string();
+        let loaded = self
+            .db_update_rights_index
+            .as_ref()
+            .is_some_and(|loaded| loaded.to_lowercase() == index.to_lowercase());
+        if self.db_update_rights_edited || loaded || self.db_connection.is_none() {
+            return;
+        }
+        self.db_update_rights = self.load_rights_holders(&index);
+        self.db_update_rights_index = Some(index);
+    }
+
     // Render database update dialog
     fn render_db_update_dialog(&mut self, ctx: &egui::Context) {
+        self.sync_db_update_rights();
         egui::Window::new("Datenbank aktualisieren")
             .resizable(true)
             .min_width(400.0)
@@ -444,6 +1701,12 @@ impl GemaLauncherApp {
                     ui.label("Labelcode:");
                     ui.text_edit_singleline(&mut self.db_update_labelcode);
                 });
+
+                ui.add_space(10.0);
+                ui.strong("Beteiligte (Komponist, Textdichter, Bearbeiter, Verlag)");
+                if rights_holders_editor(ui, "db_update_rights", &mut self.db_update_rights) {
+                    self.db_update_rights_edited = true;
+                }
                 
                 ui.add_space(10.0);
                 
@@ -482,50 +1745,45 @@ impl GemaLauncherApp {
             return;
         }
         
-        let Some(conn) = self.db_connection.as_ref() else {
+        if let Some(error) = share_errors(&self.db_update_rights).into_iter().next() {
+            self.db_update_status = format!("Fehler: {}", error);
+            return;
+        }
+        if self.db_update_rights.iter().any(|holder| holder.name.trim().is_empty()) {
+            self.db_update_status = "Fehler: Jeder Beteiligte braucht einen Namen".to_string();
+            return;
+        }
+
+        if self.db_connection.is_none() {
             self.db_update_status = "Fehler: Keine Datenbankverbindung".to_string();
             return;
-        };
-        
-        // Check if entry exists
-        let exists_query = "SELECT COUNT(*) FROM my_table WHERE LOWER(\"index\") = LOWER(?1)";
-        let exists: i64 = match conn.query_row(exists_query, params![&self.db_update_index], |row| row.get(0)) {
-            Ok(count) => count,
-            Err(e) => {
-                self.db_update_status = format!("Fehler bei Datenbankabfrage: {}", e);
-                return;
+        }
+
+        // Ohne Labelcode-Angabe den Code aus der Präfix-Tabelle übernehmen
+        if self.db_update_labelcode.trim().is_empty() {
+            if let Some(label_match) = self.label_codes.lookup(&self.db_update_index) {
+                self.db_update_labelcode = label_match.code.to_string();
             }
+        }
+
+        // Der Editor zeigt die Beteiligten des Index oder wurde bearbeitet; eine leere
+        // Liste löscht also nur Beteiligte, die der Nutzer selbst entfernt hat
+        self.sync_db_update_rights();
+
+        // Vorheriger Inhalt kommt in den Verlauf, damit sich das Speichern rückgängig machen lässt
+        let row = DatabaseRow {
+            index: self.db_update_index.clone(),
+            titel: self.db_update_title.clone(),
+            kuenstler: self.db_update_artist.clone(),
+            label_code: self.db_update_labelcode.clone(),
+            rights_holders: self.db_update_rights.clone(),
         };
-        
-        let result = if exists > 0 {
-            // Update existing entry
-            conn.execute(
-                "UPDATE my_table SET titel = ?1, kuenstler = ?2, labelcode = ?3 WHERE LOWER(\"index\") = LOWER(?4)",
-                params![
-                    &self.db_update_title,
-                    &self.db_update_artist,
-                    &self.db_update_labelcode,
-                    &self.db_update_index,
-                ],
-            )
-        } else {
-            // Insert new entry
-            conn.execute(
-                "INSERT INTO my_table (\"index\", titel, kuenstler, labelcode) VALUES (?1, ?2, ?3, ?4)",
-                params![
-                    &self.db_update_index,
-                    &self.db_update_title,
-                    &self.db_update_artist,
-                    &self.db_update_labelcode,
-                ],
-            )
-        };
-        
-        match result {
-            Ok(_) => {
-                self.db_update_status = format!("Eintrag erfolgreich {}", if exists > 0 { "aktualisiert" } else { "hinzugefügt" });
-                // Re-parse to apply database changes
-                let _ = self.parse_filenames();
+        match self.save_database_row(row) {
+            Ok(existed) => {
+                // Der Editor zeigt jetzt den gespeicherten Stand
+                self.db_update_rights_index = Some(self.db_update_index.trim().to_string());
+                self.db_update_rights_edited = false;
+                self.db_update_status = format!("Eintrag erfolgreich {}", if existed { "aktualisiert" } else { "hinzugefügt" });
             },
             Err(e) => {
                 self.db_update_status = format!("Datenbankfehler: {}", e);
@@ -535,6 +1793,7 @@ impl GemaLauncherApp {
 
     // Render database search dialog
     fn render_db_search_dialog(&mut self, ctx: &egui::Context) {
+        let mut open_update = false;
         egui::Window::new("Datenbank durchsuchen")
             .resizable(true)
             .min_width(550.0)
@@ -601,8 +1860,7 @@ impl GemaLauncherApp {
                                     self.db_update_title = title.clone();
                                     self.db_update_artist = artist.clone();
                                     self.db_update_labelcode = labelcode.clone();
-                                    self.show_db_update_dialog = true;
-                                    self.show_db_search_dialog = false;
+                                    open_update = true;
                                 }
                                 ui.end_row();
                             }
@@ -624,8 +1882,7 @@ impl GemaLauncherApp {
                         self.db_update_title = String::new();
                         self.db_update_artist = String::new();
                         self.db_update_labelcode = String::new();
-                        self.show_db_update_dialog = true;
-                        self.show_db_search_dialog = false;
+                        open_update = true;
                     }
                     
                     if ui.button("Schließen").clicked() {
@@ -633,6 +1890,11 @@ impl GemaLauncherApp {
                     }
                 });
             });
+
+        if open_update {
+            self.show_db_search_dialog = false;
+            self.open_db_update_dialog();
+        }
     }
     
     // Method to perform database search
@@ -695,10 +1957,116 @@ impl GemaLauncherApp {
             Err(_) => return,
         };
         
-        for row_result in rows {
-            if let Ok(row) = row_result {
-                self.db_search_results.push(row);
+        for row in rows.flatten() {
+            self.db_search_results.push(row);
+        }
+    }
+}
+
+fn delimiter_label(delimiter: char) -> String {
+    match delimiter {
+        '\t' => "Tabulator".to_string(),
+        c => format!("'{}'", c),
+    }
+}
+
+fn severity_color(severity: Severity) -> egui::Color32 {
+    match severity {
+        Severity::Error => egui::Color32::RED,
+        Severity::Warning => egui::Color32::from_rgb(230, 160, 0),
+        Severity::Info => egui::Color32::GRAY,
+    }
+}
+
+/// Namensfeld für Dauer-Regeln und Exportprofile. Umbenannt wird erst, wenn der Name
+/// nicht leer und nicht schon vergeben ist; bis dahin steht der Grund neben dem Feld.
+/// Gibt zurück, ob `current` geändert wurde.
+fn unique_name_editor(ui: &mut egui::Ui, edit: &mut Option<String>, current: &mut String, others: &[String]) -> bool {
+    let mut name = edit.clone().unwrap_or_else(|| current.clone());
+    let mut renamed = false;
+    ui.horizontal(|ui| {
+        let response = ui.text_edit_singleline(&mut name);
+        let trimmed = name.trim();
+        let problem = if trimmed.is_empty() {
+            Some("Name darf nicht leer sein")
+        } else if others.iter().any(|other| other == trimmed) {
+            Some("Name bereits vergeben")
+        } else {
+            None
+        };
+        if response.changed() {
+            if problem.is_none() && current != trimmed {
+                *current = trimmed.to_string();
+                renamed = true;
             }
+            *edit = Some(name.clone());
         }
+        if response.lost_focus() {
+            *edit = None;
+        } else if let Some(problem) = problem {
+            ui.colored_label(egui::Color32::RED, problem);
+        }
+    });
+    renamed
+}
+
+/// Erster freier Name "Regel 2", "Regel 3", ...; eindeutig auch nachdem Einträge gelöscht wurden.
+fn unused_name(prefix: &str, names: &[String]) -> String {
+    (names.len() + 1..)
+        .map(|n| format!("{} {}", prefix, n))
+        .find(|name| !names.contains(name))
+        .expect("unbounded range")
+}
+
+/// Tabelle zum Bearbeiten der Beteiligten: Rolle, Name, IPI-Nummer und Anteil.
+/// Gibt zurück, ob die Liste geändert wurde.
+fn rights_holders_editor(ui: &mut egui::Ui, id: &str, holders: &mut Vec<RightsHolder>) -> bool {
+    let mut changed = false;
+    let mut remove = None;
+    egui::Grid::new(id).num_columns(5).spacing([8.0, 4.0]).show(ui, |ui| {
+        ui.strong("Rolle");
+        ui.strong("Name");
+        ui.strong("IPI-Nr.");
+        ui.strong("Anteil");
+        ui.label("");
+        ui.end_row();
+
+        for (i, holder) in holders.iter_mut().enumerate() {
+            egui::ComboBox::from_id_source(format!("{}_role_{}", id, i))
+                .selected_text(holder.role.label())
+                .show_ui(ui, |ui| {
+                    for role in Role::ALL {
+                        changed |= ui.selectable_value(&mut holder.role, role, role.label()).changed();
+                    }
+                });
+            changed |= ui.text_edit_singleline(&mut holder.name).changed();
+            changed |= ui.add(egui::TextEdit::singleline(&mut holder.ipi).desired_width(110.0)).changed();
+            // ohne Haken kein Anteil angegeben; 0 % bleibt ein gültiger Anteil
+            ui.horizontal(|ui| {
+                let mut has_share = holder.share.is_some();
+                if ui.checkbox(&mut has_share, "").changed() {
+                    holder.share = has_share.then_some(0.0);
+                    changed = true;
+                }
+                if let Some(share) = holder.share.as_mut() {
+                    changed |= ui
+                        .add(egui::DragValue::new(share).clamp_range(0.0..=100.0).speed(0.5).suffix(" %"))
+                        .changed();
+                }
+            });
+            if ui.button("Entfernen").clicked() {
+                remove = Some(i);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(i) = remove {
+        holders.remove(i);
+        changed = true;
+    }
+    if ui.button("Beteiligten hinzufügen").clicked() {
+        holders.push(RightsHolder::new(Role::Composer, String::new()));
+        changed = true;
     }
-}
\ No newline at end of file
+    changed
+}
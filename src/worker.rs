@@ -0,0 +1,278 @@
+use crate::cache::{self, FileFingerprint};
+use crate::diagnostic::{Diagnostic, DiagnosticKind, Severity};
+use crate::enrich::{CandidateFinder, DatabaseLookup, MatchCandidates};
+use crate::parser::{parse_file, ParseContext};
+use log::info;
+use rusqlite::{Connection, OpenFlags};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+/// Fortschritt des laufenden Durchlaufs (je Phase: Dateien bzw. Tracks).
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    pub done: usize,
+    pub total: usize,
+    pub stage: String,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.done as f32 / self.total as f32
+        }
+    }
+}
+
+/// Eine Datei für den Durchlauf; mit bekanntem Hash wird sie nur bei
+/// geändertem Inhalt neu eingelesen.
+pub struct ParseRequest {
+    pub path: String,
+    pub known_hash: Option<u64>,
+}
+
+/// Ergebnis eines Durchlaufs: Kontext mit den neu gelesenen Dateien, die
+/// Fingerabdrücke der gelesenen und der inhaltlich unveränderten Dateien sowie
+/// Datenbank-Vorschläge je Datei für Tracks ohne sicheren Treffer.
+pub struct ParseOutcome {
+    pub ctx: ParseContext,
+    pub parsed: Vec<(String, Option<FileFingerprint>)>,
+    pub unchanged: Vec<(String, FileFingerprint)>,
+    pub candidates: HashMap<String, Vec<MatchCandidates>>,
+}
+
+pub enum WorkerMessage {
+    Progress(Progress),
+    Finished(Box<ParseOutcome>),
+    Cancelled,
+}
+
+/// Einlesen und Datenbank-Anreicherung in einem eigenen Thread.
+/// Die Ergebnisse kommen über einen Kanal zurück; die Oberfläche fragt sie
+/// in jedem Frame mit `poll` ab.
+pub struct ParseJob {
+    receiver: Receiver<WorkerMessage>,
+    cancel: Arc<AtomicBool>,
+    pub progress: Progress,
+}
+
+impl ParseJob {
+    /// Startet den Durchlauf. Die Datenbank wird im Thread mit einer eigenen
+    /// (nur lesenden) Verbindung geöffnet, da `Connection` nicht geteilt werden kann.
+    pub fn start(ctx: ParseContext, requests: Vec<ParseRequest>, db_path: Option<String>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let worker_cancel = Arc::clone(&cancel);
+
+        thread::spawn(move || run(ctx, requests, db_path, worker_cancel, sender));
+
+        Self {
+            receiver,
+            cancel,
+            progress: Progress::default(),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelling(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// Übernimmt Fortschrittsmeldungen und liefert das Endergebnis, sobald es da ist.
+    pub fn poll(&mut self) -> Option<WorkerMessage> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(WorkerMessage::Progress(progress)) => self.progress = progress,
+                Ok(message) => return Some(message),
+                Err(TryRecvError::Empty) => return None,
+                // Thread ohne Ergebnis beendet (z.B. Panic)
+                Err(TryRecvError::Disconnected) => return Some(WorkerMessage::Cancelled),
+            }
+        }
+    }
+}
+
+fn run(
+    mut ctx: ParseContext,
+    requests: Vec<ParseRequest>,
+    db_path: Option<String>,
+    cancel: Arc<AtomicBool>,
+    sender: Sender<WorkerMessage>,
+) {
+    let cancelled = || cancel.load(Ordering::Relaxed);
+    let progress = |done: usize, total: usize, stage: String| {
+        let _ = sender.send(WorkerMessage::Progress(Progress { done, total, stage }));
+    };
+
+    let mut parsed = Vec::new();
+    let mut unchanged = Vec::new();
+    let mut candidates = HashMap::new();
+    for (i, request) in requests.iter().enumerate() {
+        if cancelled() {
+            let _ = sender.send(WorkerMessage::Cancelled);
+            return;
+        }
+        let filename = &request.path;
+        let name = Path::new(filename).file_name().map_or(filename.as_str(), |n| n.to_str().unwrap_or_default());
+        progress(i, requests.len(), format!("Lese {}", name));
+
+        let fingerprint = cache::fingerprint(filename).ok();
+        if let (Some(fp), Some(known)) = (fingerprint, request.known_hash) {
+            if fp.content_hash == known {
+                info!("{} nur angefasst, Inhalt unverändert.", filename);
+                unchanged.push((filename.clone(), fp));
+                continue;
+            }
+        }
+        parse_file(&mut ctx, filename);
+        parsed.push((filename.clone(), fingerprint));
+    }
+
+    for tracks in ctx.tracks_per_file.values_mut() {
+        for track in tracks.iter_mut() {
+            track.remember_parsed();
+        }
+    }
+
+    if let Some(db_path) = db_path {
+        match Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY) {
+            Ok(conn) => match DatabaseLookup::new(&conn) {
+                Ok(mut lookup) => {
+                    let total: usize = ctx.tracks_per_file.values().map(|t| t.len()).sum();
+                    let mut done = 0;
+                    let mut unmatched = Vec::new();
+                    for (file, tracks) in ctx.tracks_per_file.iter_mut() {
+                        for (i, track) in tracks.iter_mut().enumerate() {
+                            if cancelled() {
+                                let _ = sender.send(WorkerMessage::Cancelled);
+                                return;
+                            }
+                            // nicht bei jedem Track melden, sonst läuft der Kanal voll
+                            if done % 25 == 0 {
+                                progress(done, total, "Datenbank-Abgleich".to_string());
+                            }
+                            if !lookup.enrich(track, &ctx.label_codes) {
+                                unmatched.push((file.clone(), i));
+                            }
+                            done += 1;
+                        }
+                    }
+                    if !unmatched.is_empty() {
+                        let count = unmatched.len();
+                        progress(0, count, "Suche Vorschläge".to_string());
+                        match CandidateFinder::load(&conn) {
+                            Ok(finder) => {
+                                for (done, (file, i)) in unmatched.into_iter().enumerate() {
+                                    if cancelled() {
+                                        let _ = sender.send(WorkerMessage::Cancelled);
+                                        return;
+                                    }
+                                    progress(done, count, "Suche Vorschläge".to_string());
+                                    if let Some(found) = match_fuzzy(&mut ctx, &finder, &mut lookup, &file, i) {
+                                        candidates.entry(file).or_insert_with(Vec::new).push(found);
+                                    }
+                                }
+                            }
+                            Err(e) => ctx.report(Diagnostic::warning(
+                                DiagnosticKind::Database,
+                                format!("Unscharfe Suche nicht möglich: {}", e),
+                            )),
+                        }
+                    }
+                }
+                Err(e) => ctx.report(Diagnostic::error(
+                    DiagnosticKind::Database,
+                    format!("Fehler beim Vorbereiten der Abfrage: {}", e),
+                )),
+            },
+            Err(e) => ctx.report(Diagnostic::error(
+                DiagnosticKind::Database,
+                format!("Datenbank konnte im Hintergrund nicht geöffnet werden: {}", e),
+            )),
+        }
+    } else {
+        info!("Keine Datenbankverbindung vorhanden. Überspringe Datenbank-Abgleich.");
+    }
+
+    let _ = sender.send(WorkerMessage::Finished(Box::new(ParseOutcome { ctx, parsed, unchanged, candidates })));
+}
+
+/// Sucht Vorschläge für einen Track ohne exakten Treffer. Ein Vorschlag über der
+/// Schwelle wird direkt übernommen, sonst werden die Vorschläge zurückgegeben.
+fn match_fuzzy(
+    ctx: &mut ParseContext,
+    finder: &CandidateFinder,
+    lookup: &mut DatabaseLookup,
+    file: &str,
+    i: usize,
+) -> Option<MatchCandidates> {
+    let threshold = ctx.settings.fuzzy_match_threshold;
+    let label_codes = Arc::clone(&ctx.label_codes);
+    let track = ctx.tracks_per_file.get_mut(file)?.get_mut(i)?;
+    let candidates = finder.candidates(track);
+    let Some(best) = candidates.first() else {
+        let diagnostic = Diagnostic::new(
+            Severity::Info,
+            DiagnosticKind::Database,
+            format!("'{}' nicht in der Datenbank, keine ähnlichen Einträge", track.index),
+        )
+        .file(file)
+        .track(&track.index)
+        .suggest("Eintrag über Datenbank → \"Datensatz aktualisieren\" anlegen");
+        ctx.report(diagnostic);
+        return None;
+    };
+
+    if best.confidence >= threshold {
+        let diagnostic = Diagnostic::new(
+            Severity::Info,
+            DiagnosticKind::Database,
+            format!(
+                "'{}' unscharf zugeordnet zu '{}' ({:.0} %)",
+                track.index,
+                best.index,
+                best.confidence * 100.0
+            ),
+        )
+        .file(file)
+        .track(&track.index);
+        best.apply(track, &label_codes);
+        let rights_holders = lookup.rights_holders(&best.index);
+        if !rights_holders.is_empty() {
+            track.rights_holders = rights_holders;
+        }
+        ctx.report(diagnostic);
+        return None;
+    }
+
+    let diagnostic = Diagnostic::new(
+        Severity::Info,
+        DiagnosticKind::Database,
+        format!(
+            "'{}' nicht in der Datenbank, {} Vorschläge (bester: '{}', {:.0} %)",
+            track.index,
+            candidates.len(),
+            best.index,
+            best.confidence * 100.0
+        ),
+    )
+    .file(file)
+    .track(&track.index)
+    .suggest("Vorschläge unter \"Vorschläge prüfen\" übernehmen oder verwerfen");
+    let found = MatchCandidates {
+        index: track.index.clone(),
+        titel: track.titel.clone(),
+        kuenstler: track.kuenstler.clone(),
+        candidates,
+    };
+    ctx.report(diagnostic);
+    Some(found)
+}